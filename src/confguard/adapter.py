@@ -1,6 +1,7 @@
 import logging
+import os
 from dataclasses import dataclass, field
-from datetime import datetime
+from datetime import datetime, timezone
 from pathlib import Path
 from typing import Protocol, TypeVar, runtime_checkable
 
@@ -18,6 +19,25 @@ _log = logging.getLogger(__name__)
 AggT = TypeVar("AggT")
 
 
+def _timestamp() -> datetime:
+    """Current UTC time, or SOURCE_DATE_EPOCH for reproducible output
+    An empty SOURCE_DATE_EPOCH counts as unset, anything else must be seconds.
+    """
+    epoch = os.environ.get("SOURCE_DATE_EPOCH", "")
+    if epoch == "":
+        return datetime.utcnow()
+    try:
+        seconds = int(epoch)
+        if seconds < 0:
+            raise ValueError("negative")
+        timestamp = datetime.fromtimestamp(seconds, tz=timezone.utc)
+    except (ValueError, OverflowError, OSError):
+        raise InvalidConfigError(
+            f"Invalid SOURCE_DATE_EPOCH {epoch!r}, expected seconds since 1970."
+        )
+    return timestamp.replace(tzinfo=None)
+
+
 @runtime_checkable
 class AbstractRepoSentinel(Protocol[AggT]):
     def add(self, agg: AggT) -> None:
@@ -40,9 +60,15 @@ class TomlRepoConfGuard:
         self.path = self.source_dir / CONFGUARD_CONFIG_FILE
         if not self.path.exists():
             raise FileNotFoundError(f"{self.path} does not exist")
-        with open(self.path, mode="rt", encoding="utf-8") as fp:
-            self.toml = tomlkit.load(fp)
-            _log.debug(f"{self.toml=}")
+        try:
+            with open(self.path, mode="rt", encoding="utf-8") as fp:
+                self.toml = tomlkit.load(fp)
+        except UnicodeDecodeError as e:
+            line = self.path.read_bytes().count(b"\n", 0, e.start) + 1
+            raise InvalidConfigError(
+                f"Invalid config in {self.path}, not UTF-8 in line {line} (byte {e.start})."
+            )
+        _log.debug(f"{self.toml=}")
 
     def get(self) -> ConfGuard:
         try:
@@ -54,11 +80,12 @@ class TomlRepoConfGuard:
         try:
             is_relative = self.toml["config"]["relative"]
         except NonExistentKey:
-            is_relative = False
+            is_relative = config.confguard_relative
         cg = ConfGuard(
             source_dir=self.source_dir,
             targets=targets,
             is_relative=is_relative,
+            is_copy=config.confguard_copy,
         )
         try:
             sentinel = self.toml["_internal_"]["sentinel"]
@@ -69,6 +96,11 @@ class TomlRepoConfGuard:
         cg.sentinel = sentinel
         cg.target_dir = config.confguard_path / sentinel
         cg.files = files
+        cg.is_copy = self.toml["_internal_"].get("mode") == "copy"
+        cg.is_relative = self.toml["_internal_"].get("relative", is_relative)
+        checksums = self.toml["_internal_"].get("checksums")
+        if checksums is not None:
+            cg.checksums = {str(k): str(v) for k, v in checksums.items()}
         _log.debug(f"{sentinel=}, {targets=}, {files=}")
         return cg
 
@@ -82,7 +114,7 @@ class TomlRepoConfGuard:
             else:  # new
                 intern = table()
                 intern.add("sentinel", confguard.sentinel)
-                intern.add("timestamp", datetime.utcnow())
+                intern.add("timestamp", _timestamp())
                 intern.add(
                     "files",
                     tomlkit.string(
@@ -92,6 +124,16 @@ class TomlRepoConfGuard:
                 )
                 self.toml["_internal_"] = intern
                 self.toml["_internal_"].comment("DO NOT EDIT FROM HERE")
+            if confguard.is_copy:
+                self.toml["_internal_"]["mode"] = "copy"
+            elif "mode" in self.toml["_internal_"]:
+                del self.toml["_internal_"]["mode"]
+            self.toml["_internal_"]["relative"] = confguard.is_relative  # link style
+            if confguard.checksums is not None:
+                checksums = table()
+                for rel_path, checksum in confguard.checksums.items():
+                    checksums.add(rel_path, checksum)
+                self.toml["_internal_"]["checksums"] = checksums
 
         else:  # delete sentinel
             try:
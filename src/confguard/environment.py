@@ -15,6 +15,7 @@ ROOT_DIR = Path(__file__).parent.parent.parent.absolute()
 FINGERPRINT = ".*.confguard"  # identifier for sentinel files
 CONFGUARD_CONFIG_FILE = ".confguard"
 CONFGUARD_BKP_DIR = "_confguard.tmp.bkp"
+COPY_STUB_HEADER = "# confguard: guarded file, do not edit"  # marks copy-mode stubs
 
 RUN_ENV = os.environ.get("RUN_ENV", "local").lower()
 assert RUN_ENV in RUN_ENVS, f"RUN_ENV must be one of {RUN_ENVS}"
@@ -22,15 +23,35 @@ assert RUN_ENV in RUN_ENVS, f"RUN_ENV must be one of {RUN_ENVS}"
 CONFIG_TEMPLATE = """\
 #  vim: set ts=4 sw=4 tw=120 et ft=toml:
 [config]
-files = ['.envrc', '.run']
+targets = ['.envrc', '.run']
 """
 
 
+def default_confguard_path() -> Path:
+    """XDG data directory, used if CONFGUARD_PATH is not set"""
+    xdg_data_home = Path(os.environ.get("XDG_DATA_HOME", ""))
+    if not xdg_data_home.is_absolute():  # unset or invalid per XDG spec
+        xdg_data_home = Path.home() / ".local/share"
+    return xdg_data_home / "confguard"
+
+
+def default_protected_paths() -> list[Path]:
+    """Never guarded: the home directory itself and system directories"""
+    system = ("/bin", "/boot", "/dev", "/etc", "/lib", "/proc", "/sbin", "/sys", "/usr")
+    return [Path("/"), Path.home(), *(Path(p) for p in system)]
+
+
 class Environment(BaseSettings):
     app_name: str = "confguard"
     log_level: str = "INFO"
     twbm_db_url: str = "sqlite:///db/bm.db"
-    confguard_path: Path
+    confguard_path: Path = pydantic.Field(default_factory=default_confguard_path)
+    confguard_relative: bool = False  # link style if not set in .confguard
+    confguard_sentinel_by_path: bool = False  # hash of source path instead of uuid
+    confguard_copy: bool = False  # stubs which source the guarded file, no symlinks
+    confguard_protected_paths: list[Path] = pydantic.Field(
+        default_factory=default_protected_paths
+    )
     confguard: TOMLDocument = {}
 
     # init
@@ -52,12 +73,16 @@ class Environment(BaseSettings):
         return sanitized_cfg
 
 
-try:
-    config = Environment()
-except pydantic.error_wrappers.ValidationError as e:
-    typer.secho(
-        f"CONFIGURATION ERROR: Make sure environment variable CONFGUARD_PATH is set.",
-        fg=typer.colors.RED,
-        err=True,
-    )
-    sys.exit(1)
+def load_config() -> Environment:
+    """Exits with the failing variable if the environment is invalid"""
+    try:
+        return Environment()
+    except (
+        pydantic.error_wrappers.ValidationError,
+        pydantic.env_settings.SettingsError,
+    ) as e:
+        typer.secho(f"CONFIGURATION ERROR: {e}", fg=typer.colors.RED, err=True)
+        sys.exit(1)
+
+
+config = load_config()
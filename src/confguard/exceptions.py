@@ -1,14 +1,52 @@
 class ConfGuardError(Exception):
     """A base class for MyProject exceptions."""
 
+    code = "error"  # stable identifier for --json error output
+
 
 class BackupExistError(ConfGuardError):
     """A custom exception class for MyProject."""
 
+    code = "backup_exists"
+
 
 class DirectoryNotDeleted(ConfGuardError):
     """A custom exception class for MyProject."""
 
+    code = "directory_not_deleted"
+
 
 class InvalidConfigError(ConfGuardError):
     """A custom exception class for MyProject."""
+
+    code = "invalid_config"
+
+
+class TargetOutsideBaseError(ConfGuardError):
+    """Target directory is not inside CONFGUARD_PATH."""
+
+    code = "target_outside_base"
+
+
+class SentinelMismatchError(ConfGuardError):
+    """Sentinel backup belongs to a different sentinel."""
+
+    code = "sentinel_mismatch"
+
+
+class SourceDirNotWritableError(ConfGuardError):
+    """Project directory cannot be modified."""
+
+    code = "not_writable"
+
+
+class SentinelExistsError(ConfGuardError):
+    """Sentinel directory is already in use."""
+
+    code = "sentinel_exists"
+
+
+class ProtectedPathError(ConfGuardError):
+    """Project directory is a protected path."""
+
+    code = "protected_path"
@@ -0,0 +1,55 @@
+import csv
+import io
+import json
+from enum import Enum
+
+from confguard.model import ConfGuard
+
+
+class OutputFormat(str, Enum):
+    table = "table"
+    json = "json"
+    csv = "csv"
+
+
+class SortKey(str, Enum):
+    name = "name"
+    date = "date"
+    source = "source"
+
+
+def to_row(cg: ConfGuard) -> dict[str, str]:
+    return {
+        "sentinel": str(cg.sentinel),
+        "source_dir": str(cg.source_dir),
+        "target_dir": str(cg.target_dir),
+    }
+
+
+def render(rows: list[dict[str, str]], fmt: OutputFormat) -> str:
+    if fmt == OutputFormat.json:
+        return json.dumps(rows, indent=2)
+    if fmt == OutputFormat.csv:
+        return _render_csv(rows)
+    return _render_table(rows)
+
+
+def _render_csv(rows: list[dict[str, str]]) -> str:
+    if len(rows) == 0:
+        return ""
+    buf = io.StringIO()
+    writer = csv.DictWriter(buf, fieldnames=list(rows[0]), lineterminator="\n")
+    writer.writeheader()
+    writer.writerows(rows)
+    return buf.getvalue().rstrip("\n")
+
+
+def _render_table(rows: list[dict[str, str]]) -> str:
+    if len(rows) == 0:
+        return ""
+    headers = list(rows[0])
+    widths = {h: max(len(h), *(len(r[h]) for r in rows)) for h in headers}
+    lines = ["  ".join(h.upper().ljust(widths[h]) for h in headers).rstrip()]
+    for r in rows:
+        lines.append("  ".join(r[h].ljust(widths[h]) for h in headers).rstrip())
+    return "\n".join(lines)
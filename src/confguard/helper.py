@@ -1,9 +1,15 @@
 import base64
+import logging
 import os
 import pickle
+import tempfile
 import textwrap
+import time
+from contextlib import contextmanager
 from pathlib import Path
-from typing import Any
+from typing import Any, Iterator, Optional
+
+_log = logging.getLogger(__name__)
 
 
 def serialize_to_base64(obj: Any, line_length=80) -> str:
@@ -38,6 +44,51 @@ def _create_relative_path(source: str, target: str) -> Path:
     return Path(rel_path) / name
 
 
+@contextmanager
+def _timed(phase: str) -> Iterator[None]:
+    """Logs the wall-clock duration of `phase` at debug level (--verbose)"""
+    start = time.perf_counter()
+    try:
+        yield
+    finally:
+        _log.debug(f"{phase} took {time.perf_counter() - start:.3f}s")
+
+
+def _is_protected(path: Path, protected: list[Path]) -> bool:
+    """A protected path covers its subtree, unless it contains the home directory"""
+    path = path.resolve()
+    home = Path.home().resolve()
+    for p in (p.resolve() for p in protected):
+        if path == p:
+            return True
+        if p in path.parents and not (p == home or p in home.parents):
+            return True
+    return False
+
+
+def _is_writable(dir_: Path) -> bool:
+    """Probes with a temporary file, os.access misses ACLs and read-only mounts"""
+    try:
+        with tempfile.TemporaryFile(dir=dir_):
+            return True
+    except OSError:
+        return False
+
+
+def _find_linked_worktree(path: Path) -> Optional[Path]:
+    """Root of the linked git worktree containing `path`, None for a main checkout"""
+    for dir_ in (path, *path.parents):
+        git = dir_ / ".git"
+        if git.is_dir():
+            return None
+        if git.is_file():
+            content = git.read_text(encoding="utf-8").strip()
+            if content.startswith("gitdir:") and "/worktrees/" in content:
+                return dir_
+            return None  # submodule
+    return None
+
+
 if __name__ == "__main__":
     # Create a list of strings
     files = [
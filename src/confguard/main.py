@@ -1,25 +1,116 @@
+import json
 import logging
+import os
+from datetime import datetime
 from pathlib import Path
+from typing import NoReturn, Optional
 
+import click
 import typer
 from rich.console import Console
 from rich.logging import RichHandler
 from rich.theme import Theme
 
-from confguard.adapter import TomlRepoConfGuard
-from confguard.environment import CONFGUARD_BKP_DIR, CONFGUARD_CONFIG_FILE, config
-from confguard.exceptions import InvalidConfigError
+from confguard.adapter import TomlRepoConfGuard, _timestamp
+from confguard.environment import (
+    CONFGUARD_BKP_DIR,
+    CONFGUARD_CONFIG_FILE,
+    CONFIG_TEMPLATE,
+    config,
+)
+from confguard.exceptions import (
+    BackupExistError,
+    InvalidConfigError,
+    ProtectedPathError,
+    SentinelExistsError,
+    SentinelMismatchError,
+    SourceDirNotWritableError,
+    TargetOutsideBaseError,
+)
+from confguard.formatter import OutputFormat, SortKey, render, to_row
+from confguard.helper import _find_linked_worktree, _timed
 from confguard.model import ConfGuard
 
 _log = logging.getLogger(__name__)
 app = typer.Typer(help="Save sensitive configuration in a save place")
 
+# allowed in read-only mode, any other command is refused until classified here
+READ_ONLY_COMMANDS = ("list", "show", "verify", "which")
+
+
+# exit status per error code, 1 for all others, see README
+EXIT_CODES = {
+    "config_not_found": 3,  # not found
+    "project_not_found": 3,
+    "backup_exists": 4,  # already exists
+    "sentinel_exists": 4,
+    "not_guarded": 5,
+    "linked_worktree": 6,  # refused
+    "protected_path": 6,
+    "not_writable": 6,
+    "read_only": 6,
+}
+
+
+def _fail(message: str, code: str) -> NoReturn:
+    """Reports an error on stderr and exits, as JSON object with --json"""
+    ctx = click.get_current_context(silent=True)
+    if ctx is not None and ctx.find_root().params.get("json_errors"):
+        error = {"error": {"code": code, "message": message}}
+        typer.echo(json.dumps(error), err=True)
+    else:
+        typer.secho(message, fg=typer.colors.RED, err=True)
+    raise typer.Exit(EXIT_CODES.get(code, 1))
+
+
+def _parse_mode(value: Optional[str]) -> Optional[int]:
+    if value is None:
+        return None
+    try:
+        mode = int(value, 8)
+    except ValueError:
+        raise typer.BadParameter(f"{value} is not an octal mode")
+    if not 0 <= mode <= 0o7777:
+        raise typer.BadParameter(f"{value} is out of range")
+    return mode
+
 
 @app.command()
 def guard(
     source_dir: Path = typer.Argument(
         ..., help="Path to the directory to guard", exists=True
     ),
+    strict: bool = typer.Option(
+        False, "--strict", help="Refuse to guard a linked git worktree"
+    ),
+    print_path: bool = typer.Option(
+        False, "--print-path", help="Only print the sentinel directory"
+    ),
+    relative: Optional[bool] = typer.Option(
+        None,
+        "--relative/--absolute",
+        help="Link style, overrides `relative` in .confguard and CONFGUARD_RELATIVE",
+        show_default=False,
+    ),
+    chmod: Optional[str] = typer.Option(
+        None,
+        "--chmod",
+        help="Octal mode for the guarded files, e.g. 600",
+        callback=_parse_mode,
+    ),
+    verify_after: bool = typer.Option(
+        False, "--verify-after", help="Check the result, roll back on failure"
+    ),
+    init: bool = typer.Option(
+        False, "--init", help=f"Create a default {CONFGUARD_CONFIG_FILE} if missing"
+    ),
+    copy: Optional[bool] = typer.Option(
+        None,
+        "--copy/--link",
+        help="Write stubs which source the guarded .envrc instead of symlinks, "
+        "overrides CONFGUARD_COPY",
+        show_default=False,
+    ),
 ):
     """Guards a directory.
     Configuration: `.confguard` in project directory
@@ -27,62 +118,159 @@ def guard(
     CAVEAT: relative linking cannot span mounts, absolute linking can
     """
     source_dir = Path(source_dir).expanduser().resolve()
-    if not (source_dir / CONFGUARD_CONFIG_FILE).exists():
-        typer.secho(
+    worktree = _find_linked_worktree(source_dir)
+    if worktree is not None:
+        message = f"{worktree} is a linked git worktree, guarding may affect sibling worktrees."
+        if strict:
+            _fail(message, "linked_worktree")
+        typer.secho(message, fg=typer.colors.YELLOW, err=True)
+    config_file = source_dir / CONFGUARD_CONFIG_FILE
+    created = init and not config_file.exists()
+    if created:
+        config_file.write_text(CONFIG_TEMPLATE, encoding="utf-8")
+        _log.info(f"Created {config_file}")
+    if not config_file.exists():
+        _fail(
             f"Configuration file {CONFGUARD_CONFIG_FILE} not found in {source_dir}",
-            fg=typer.colors.RED,
-            err=True,
+            "config_not_found",
         )
-        raise typer.Exit(1)
-    cg = _guard(source_dir)
+    try:
+        cg = _guard(
+            source_dir, is_relative=relative, mode=chmod, quiet=print_path, copy=copy
+        )
+        if verify_after:
+            _verify_or_rollback(cg)
+    except typer.Exit as e:
+        if e.exit_code == 0 and print_path:  # already guarded, still report the path
+            typer.echo(TomlRepoConfGuard(source_dir=source_dir).get().target_dir)
+        if e.exit_code != 0 and created:
+            _remove_unguarded_config(config_file)
+        raise
+    if print_path:
+        typer.echo(cg.target_dir)
+        return
     typer.secho(
         f"Project {source_dir} is now guarded. Sensitive files are now in {cg.target_dir}",
         fg=typer.colors.GREEN,
     )
 
 
-def _guard(source_dir: Path) -> ConfGuard:
-    repo = TomlRepoConfGuard(source_dir=source_dir)
+def _remove_unguarded_config(config_file: Path) -> None:
+    """Removes a `.confguard` created by --init, unless guarding got that far"""
+    try:
+        guarded = TomlRepoConfGuard(source_dir=config_file.parent).get().sentinel
+    except Exception as e:
+        _log.warning(f"Keeping {config_file}: {e}")
+        return
+    if guarded is None:
+        _log.info(f"Removing {config_file}, the project has not been guarded.")
+        config_file.unlink(missing_ok=True)
+
+
+def _verify_or_rollback(cg: ConfGuard) -> None:
+    problems = _verify_guarded(cg)
+    if len(problems) == 0:
+        return
+    _log.error(f"Verification failed, rolling back: {problems}")
+    try:
+        _rollback_guard(cg)
+    except Exception as e:
+        _fail(
+            f"Verification failed: {problems}. Rollback failed, "
+            f"manual intervention required: {e}",
+            "verify_failed",
+        )
+    _fail(f"Verification failed, rolled back: {problems}", "verify_failed")
+
+
+def _guard(
+    source_dir: Path,
+    is_relative: Optional[bool] = None,
+    mode: Optional[int] = None,
+    quiet: bool = False,
+    copy: Optional[bool] = None,
+) -> ConfGuard:
     try:
+        repo = TomlRepoConfGuard(source_dir=source_dir)
         cg = repo.get()
+        _timestamp()  # saved after the files are moved, so validate it up front
     except InvalidConfigError as e:
-        typer.secho(str(e), fg=typer.colors.RED, err=True)
-        raise typer.Exit(1)
+        _fail(str(e), e.code)
+    if is_relative is not None:
+        cg.is_relative = is_relative
+    if copy is not None:
+        cg.is_copy = copy
+
+    try:
+        cg.check_copy_targets()
+    except InvalidConfigError as e:
+        _fail(str(e), e.code)
+
+    try:
+        cg.check_protected()
+        cg.check_writable()
+    except (ProtectedPathError, SourceDirNotWritableError) as e:
+        _fail(f"Cannot guard, Aborting: {e}", e.code)
 
     if cg.sentinel is not None:
         if cg.files == cg.targets:
-            typer.secho(
-                f"Project is already guarded, nothing to do.",
-                fg=typer.colors.GREEN,
-            )
+            if not quiet:
+                typer.secho(
+                    f"Project is already guarded, nothing to do.",
+                    fg=typer.colors.GREEN,
+                )
             raise typer.Exit(0)
         else:
             _log.debug(f"Project is already guarded, but not all files are guarded.")
             _unguard(source_dir)  # get everything back and recreate with new config
+            if is_relative is None:  # configured link style, not the saved one
+                configured = TomlRepoConfGuard(source_dir=source_dir).get()
+                cg.is_relative = configured.is_relative
+
+    existing = [
+        g.sentinel for g in _list_guarded() if g.source_dir == source_dir.resolve()
+    ]
+    if len(existing) > 0:  # state lost, guarding again would move the links
+        _fail(
+            f"{source_dir} is already guarded by {', '.join(existing)}. "
+            f"Run find-and-link to restore its {CONFGUARD_CONFIG_FILE}.",
+            "sentinel_exists",
+        )
 
     _log.info(f"Guarding {source_dir}")
 
-    cg.create_sentinel()
     try:
-        cg.create_bkp(cg.source_dir, cg.targets)
-    except Exception as e:
-        typer.secho(f"Error occurred, Aborting: {e}", fg=typer.colors.RED)
+        cg.create_sentinel()
+    except SentinelExistsError as e:
+        _fail(str(e), e.code)
+    try:
+        with _timed("create_bkp"):
+            cg.create_bkp(cg.source_dir, cg.targets)
+    except BackupExistError as e:  # left by an interrupted run, do not delete it
+        _fail(f"{e} Check and remove it before guarding.", e.code)
+    except (Exception, KeyboardInterrupt) as e:
         cg.delete_dir(dir_=cg.source_dir / CONFGUARD_BKP_DIR)
         cg.remove_sentinel()
         repo.add(cg)  # save it
-        raise typer.Abort(1)
+        _fail(f"Error occurred, Aborting: {e}", "guard_failed")
 
     try:
-        cg.move_files()
-        cg.create_lk(cg.targets)
+        with _timed("move_files"):
+            cg.move_files()
+        if mode is not None:
+            cg.chmod_files(mode)
+        with _timed("compute_checksums"):
+            cg.checksums = cg.compute_checksums(cg.targets)
+        with _timed("create_lk"):
+            cg.create_lk([t for t in cg.targets if (cg.target_dir / t).exists()])
         cg.back_create()
-    except Exception as e:
-        typer.secho(f"Error occurred, rolling back: {e}", fg=typer.colors.RED)
+    except (Exception, KeyboardInterrupt) as e:
+        _log.debug(f"Error occurred, rolling back: {e!r}")
         cg.remove_lk(cg.targets)
         cg.back_remove()
         cg.restore_bkp(cg.source_dir, cg.targets)
         cg.remove_sentinel()
-        raise typer.Abort(1)
+        _fail(f"Error occurred, rolled back: {e}", "guard_failed")
     finally:
         repo.add(cg)  # save it
         cg.delete_dir(dir_=cg.source_dir / CONFGUARD_BKP_DIR)
@@ -90,6 +278,34 @@ def _guard(source_dir: Path) -> ConfGuard:
     return cg
 
 
+def _verify_guarded(cg: ConfGuard) -> list[str]:
+    """Problems of a freshly guarded project, empty if it is consistent"""
+    problems = []
+    saved = TomlRepoConfGuard(source_dir=cg.source_dir).get()
+    if saved.sentinel != cg.sentinel:
+        return [f"{CONFGUARD_CONFIG_FILE}: sentinel not saved"]
+    if (cg.target_dir / f".{cg.sentinel}.confguard").resolve() != cg.source_dir:
+        problems.append(f"{cg.target_dir}: no link back to project")
+    for rel_path, status in saved.file_status().items():
+        if status in ("no link", "wrong link") or (
+            status == "not in sentinel" and saved.linked_to(rel_path) is not None
+        ):
+            problems.append(f"{rel_path}: {status}")
+    return problems
+
+
+def _rollback_guard(cg: ConfGuard) -> None:
+    """Reverts `_guard` from the returned state, the saved state may be broken"""
+    cg.check_target_dir()
+    cg.remove_lk(cg.targets)
+    cg.back_remove()
+    moved = [t for t in cg.targets if (cg.target_dir / t).exists()]
+    cg._move_files(cg.target_dir, cg.source_dir, moved)
+    cg.delete_dir(dir_=cg.target_dir)
+    cg.remove_sentinel()
+    TomlRepoConfGuard(source_dir=cg.source_dir).add(cg)
+
+
 @app.command()
 def unguard(
     # path argument
@@ -109,54 +325,100 @@ def unguard(
 
 
 def _unguard(source_dir: Path) -> ConfGuard:
-    repo = TomlRepoConfGuard(source_dir=source_dir)
     try:
+        repo = TomlRepoConfGuard(source_dir=source_dir)
         cg = repo.get()
+    except FileNotFoundError:
+        _fail(
+            f"Configuration file {CONFGUARD_CONFIG_FILE} not found in {source_dir}",
+            "config_not_found",
+        )
     except InvalidConfigError as e:
-        typer.secho(str(e), fg=typer.colors.RED, err=True)
-        raise typer.Exit(1)
+        _fail(str(e), e.code)
 
     if cg.sentinel is None:
-        typer.secho(
-            f"Project is not guarded, nothing to do.",
-            fg=typer.colors.GREEN,
+        _fail(f"Project is not guarded, nothing to do.", "not_guarded")
+
+    try:
+        cg.check_target_dir()
+    except TargetOutsideBaseError as e:
+        _fail(f"Invalid sentinel, Aborting: {e}", e.code)
+
+    broken = cg.broken_links()
+    if len(broken) > 0:
+        _fail(
+            f"Files missing in {cg.target_dir}, Aborting: {broken}. "
+            f"Restore them or remove their links in {source_dir}.",
+            "files_missing",
+        )
+    conflicts = cg.conflicts()
+    if len(conflicts) > 0:
+        _fail(
+            f"Not a link in {source_dir}, Aborting: {conflicts}. Resolve manually.",
+            "not_a_link",
         )
-        raise typer.Exit(1)
 
     _log.info(f"Un-guarding {source_dir}")
 
     try:
-        cg.create_bkp(cg.target_dir, cg.files)
-    except Exception as e:
-        typer.secho(f"Error occurred, Aborting: {e}", fg=typer.colors.RED)
+        with _timed("create_bkp"):
+            cg.create_bkp(cg.target_dir, cg.files)
+    except BackupExistError as e:  # left by an interrupted run, do not delete it
+        _fail(f"{e} Check and remove it before un-guarding.", e.code)
+    except (Exception, KeyboardInterrupt) as e:
         cg.delete_dir(dir_=cg.target_dir / CONFGUARD_BKP_DIR)
         cg.remove_sentinel()
         repo.add(cg)  # save it
-        raise typer.Abort(1)
+        _fail(f"Error occurred, Aborting: {e}", "unguard_failed")
 
     try:
         cg.remove_lk(cg.files)
         cg.back_remove()
-        cg.unmove_files()
+        with _timed("unmove_files"):
+            cg.unmove_files()
         cg.remove_sentinel()
-    except Exception as e:
-        _log.error(f"Error occurred, rolling back: {e}")
+    except (Exception, KeyboardInterrupt) as e:
+        _log.debug(f"Error occurred, rolling back: {e!r}")
         cg.restore_bkp(cg.target_dir, cg.files)
         try:
             cg.create_lk(cg.files)
-        except Exception as e:
-            _log.warning(f"Manual intervention required: {e}")
+        except Exception as err:
+            _log.warning(f"Manual intervention required: {err}")
         try:
             cg.back_create()
-        except Exception as e:
-            _log.error(f"Manual intervention required: {e}")
-        raise typer.Abort(1)
+        except Exception as err:
+            _log.error(f"Manual intervention required: {err}")
+        _fail(f"Error occurred, rolled back: {e}", "unguard_failed")
     finally:
         repo.add(cg)  # save it
         cg.delete_dir(dir_=cg.target_dir / CONFGUARD_BKP_DIR)
     return cg
 
 
+@app.command()
+def unguard_all() -> None:
+    """Un-guards all projects in CONFGUARD_PATH.
+    Continues past failing projects and exits non-zero if any project failed.
+    """
+    failed = _unguard_all()
+    if len(failed) > 0:
+        _fail(f"Failed to un-guard {', '.join(map(str, failed))}", "unguard_failed")
+    typer.secho("All projects are now un-guarded.", fg=typer.colors.GREEN)
+
+
+def _unguard_all() -> list[Path]:
+    """Returns the source directories which could not be un-guarded"""
+    failed = []
+    for cg in _list_guarded():
+        try:
+            _unguard(cg.source_dir)
+            typer.echo(f"Un-guarded {cg.source_dir}")
+        except Exception as e:
+            _log.debug(f"Un-guarding {cg.source_dir} failed: {e!r}")
+            failed.append(cg.source_dir)
+    return failed
+
+
 @app.command()
 def find_and_link(
     source_dir: Path = typer.Argument(
@@ -178,32 +440,280 @@ def _find_and_link(source_dir: Path) -> ConfGuard:
     projects = [
         p
         for p in Path(config.confguard_path).glob("*")
-        if p.name.split("-")[0] == source_dir.name
+        if p.name.rsplit("-", 1)[0] == source_dir.name
     ]
+    if len(projects) > 1:  # same-named projects: prefer the one recorded for source_dir
+        recorded = [
+            p
+            for p in projects
+            if (p / f".{p.name}.confguard").resolve() == source_dir.resolve()
+        ]
+        if len(recorded) > 0:  # none if the project has been moved
+            projects = recorded
     if len(projects) > 1:
-        typer.secho(
+        _fail(
             f"Found more than one project for {source_dir.name}, resolve manually.",
-            fg=typer.colors.RED,
+            "ambiguous_project",
         )
-        raise typer.Exit(1)
     if len(projects) == 0:
-        typer.secho(
+        _fail(
             f"No matching project found in {config.confguard_path} for {source_dir.name}. Start guarding your project.",
-            fg=typer.colors.RED,
+            "project_not_found",
         )
-        raise typer.Exit(1)
     project = projects[0]
     _log.info(f"Found guarded project files for {project}, re-linking it.")
 
-    ConfGuard.restore_toml(source_dir, project)
-    _ = _unguard(source_dir)
-    return _guard(source_dir)
+    try:
+        ConfGuard.restore_toml(source_dir, project)
+    except FileNotFoundError as e:
+        _fail(f"Cannot restore configuration from {project}: {e}", "config_not_found")
+    except SentinelMismatchError as e:
+        _fail(f"Cannot restore configuration from {project}: {e}", e.code)
+    cg = _unguard(source_dir)
+    return _guard(source_dir, copy=cg.is_copy)
+
+
+@app.command()
+def relink_all() -> None:
+    """Re-creates missing links of all projects in CONFGUARD_PATH.
+    Use after restoring projects without their symlinks, correct links are untouched.
+    """
+    results = _relink_all()
+    for source_dir, outcome in results.items():
+        typer.echo(f"{outcome}: {source_dir}")
+    failed = [str(p) for p, outcome in results.items() if outcome == "failed"]
+    if len(failed) > 0:
+        _fail(f"Failed to re-link {', '.join(failed)}", "relink_failed")
+
+
+def _relink_all() -> dict[Path, str]:
+    """Outcome per project: relinked, skipped or failed"""
+    results = {}
+    for sentinel in _list_guarded():
+        source_dir = sentinel.source_dir
+        if not source_dir.is_dir():
+            _log.warning(f"{source_dir} does not exist, skipping {sentinel.sentinel}.")
+            results[source_dir] = "skipped"
+            continue
+        try:
+            results[source_dir] = _relink(source_dir, sentinel.target_dir)
+        except Exception as e:
+            _log.warning(f"Re-linking {source_dir} failed: {e}")
+            results[source_dir] = "failed"
+    return results
+
+
+def _relink(source_dir: Path, target_dir: Path) -> str:
+    if not (source_dir / CONFGUARD_CONFIG_FILE).exists():
+        ConfGuard.restore_toml(source_dir, target_dir)
+    cg = TomlRepoConfGuard(source_dir=source_dir).get()
+    if cg.target_dir != target_dir:
+        raise SentinelMismatchError(
+            f"{source_dir} is guarded by {cg.sentinel}, not {target_dir.name}."
+        )
+
+    broken = [
+        f for f, st in cg.file_status().items() if st in ("no link", "wrong link")
+    ]
+    if len(broken) == 0:
+        return "skipped"
+    conflicts = [
+        f
+        for f in broken
+        if (source_dir / f).exists()
+        and not (source_dir / f).is_symlink()
+        and cg.linked_to(f) is None
+    ]
+    if len(conflicts) > 0:
+        raise FileExistsError(f"Not a link, resolve manually: {conflicts}")
+
+    _log.info(f"Re-linking {broken} in {source_dir}")
+    cg.remove_lk(broken)
+    cg.create_lk(broken)
+    return "relinked"
+
+
+@app.command()
+def show(
+    source_dir: Path = typer.Argument(
+        ..., help="Path to the guarded directory", exists=True
+    ),
+    fmt: OutputFormat = typer.Option(
+        OutputFormat.table, "--format", help="Output format"
+    ),
+) -> None:
+    """Shows the files in the sentinel of a guarded project and their link status."""
+    source_dir = Path(source_dir).expanduser().resolve()
+    cg = _load_guarded(source_dir)
+    rows = [{"file": f, "status": st} for f, st in cg.file_status().items()]
+    output = render(rows, fmt)
+    if output:
+        typer.echo(output)
+
+
+@app.command()
+def which(
+    source_dir: Path = typer.Argument(
+        ..., help="Path to the guarded directory", exists=True
+    ),
+) -> None:
+    """Prints the sentinel of a guarded project and its directory."""
+    source_dir = Path(source_dir).expanduser().resolve()
+    cg = _load_guarded(source_dir)
+    typer.echo(cg.sentinel)
+    typer.echo(cg.target_dir)
+
+
+@app.command()
+def verify(
+    ctx: typer.Context,
+    source_dir: Path = typer.Argument(
+        ..., help="Path to the guarded directory", exists=True
+    ),
+    fmt: OutputFormat = typer.Option(
+        OutputFormat.table, "--format", help="Output format"
+    ),
+    fix: bool = typer.Option(
+        False, "--fix", help="Re-create missing or wrong links before checking"
+    ),
+) -> None:
+    """Checks the files in the sentinel against the checksums recorded by `guard`.
+    Exits non-zero if a file was modified, deleted or added outside of confguard.
+    """
+    if fix and ctx.find_root().params.get("read_only"):  # --fix changes links
+        _fail("Read-only mode, refusing to run verify --fix.", "read_only")
+    source_dir = Path(source_dir).expanduser().resolve()
+    cg = _load_guarded(source_dir)
+    if fix:
+        try:
+            outcome = _relink(source_dir, cg.target_dir)
+        except Exception as e:
+            _fail(f"Cannot fix {source_dir}: {e}", "fix_failed")
+        _log.info(f"Links of {source_dir}: {outcome}")
+        cg = _load_guarded(source_dir)
+    if cg.checksums is None:
+        _fail(
+            f"No checksums recorded for {source_dir}, re-guard it to record them.",
+            "no_checksums",
+        )
+    status = cg.verify_checksums()
+    rows = [{"file": f, "status": st} for f, st in status.items()]
+    output = render(rows, fmt)
+    if output:
+        typer.echo(output)
+    changed = [f for f, st in status.items() if st != "ok"]
+    if len(changed) > 0:
+        _fail(f"Files changed in {cg.target_dir}: {changed}", "modified")
+    if fix:
+        broken = [
+            f for f, st in cg.file_status().items() if st in ("no link", "wrong link")
+        ]
+        if len(broken) > 0:
+            _fail(f"Links still broken in {source_dir}: {broken}", "fix_failed")
+
+
+def _load_guarded(source_dir: Path) -> ConfGuard:
+    try:
+        repo = TomlRepoConfGuard(source_dir=source_dir)
+        cg = repo.get()
+    except FileNotFoundError:
+        _fail(
+            f"Configuration file {CONFGUARD_CONFIG_FILE} not found in {source_dir}",
+            "config_not_found",
+        )
+    except InvalidConfigError as e:
+        _fail(str(e), e.code)
+    if cg.sentinel is None:
+        _fail(f"Project {source_dir} is not guarded.", "not_guarded")
+    return cg
+
+
+@app.command(name="list")
+def list_(
+    fmt: OutputFormat = typer.Option(
+        OutputFormat.table, "--format", help="Output format"
+    ),
+    sort: SortKey = typer.Option(
+        SortKey.date, "--sort", help="Sort order, date is most recent first"
+    ),
+    reverse: bool = typer.Option(False, "--reverse", help="Reverse sort order"),
+) -> None:
+    """Lists guarded projects.
+    Only valid sentinels in CONFGUARD_PATH are reported, stray entries are ignored.
+    """
+    guarded = _sort_guarded(_list_guarded(), sort, reverse)
+    output = render([to_row(cg) for cg in guarded], fmt)
+    if output:
+        typer.echo(output)
+    if fmt == OutputFormat.table:
+        typer.secho(
+            f"{len(guarded)} guarded project(s) in {config.confguard_path}",
+            fg=typer.colors.GREEN,
+        )
+
+
+def _sort_guarded(
+    guarded: list[ConfGuard], sort: SortKey, reverse: bool = False
+) -> list[ConfGuard]:
+    if sort == SortKey.date:  # most recent first
+        return sorted(
+            guarded,
+            key=lambda cg: cg.guarded_at() or datetime.min,
+            reverse=not reverse,
+        )
+    if sort == SortKey.source:
+        return sorted(guarded, key=lambda cg: str(cg.source_dir), reverse=reverse)
+    return sorted(guarded, key=lambda cg: str(cg.sentinel), reverse=reverse)
+
+
+def _list_guarded() -> list[ConfGuard]:
+    """Sentinel directories in CONFGUARD_PATH which carry a backlink to their project"""
+    guarded = []
+    for p in sorted(Path(config.confguard_path).glob("*")):
+        backlink = p / f".{p.name}.confguard"
+        if not (p.is_dir() and backlink.is_symlink()):
+            _log.debug(f"{p} is not a sentinel, skipping.")
+            continue
+        guarded.append(
+            ConfGuard(
+                source_dir=backlink.resolve(),
+                target_dir=p,
+                sentinel=p.name,
+            )
+        )
+    return guarded
 
 
 @app.callback()
 def main(
+    ctx: typer.Context,
     verbose: bool = typer.Option(False, "-v", "--verbose", help="verbosity"),
+    no_color: bool = typer.Option(
+        False, "--no-color", help="Disable colors, also set by NO_COLOR"
+    ),
+    trace_file: Optional[Path] = typer.Option(
+        None,
+        "--trace-file",
+        envvar="CONFGUARD_TRACE_FILE",
+        help="Append debug logs to this file, independent of --verbose",
+        dir_okay=False,
+    ),
+    read_only: bool = typer.Option(
+        False,
+        "--read-only",
+        envvar="CONFGUARD_READONLY",
+        help="Refuse commands which modify projects or CONFGUARD_PATH",
+    ),
+    json_errors: bool = typer.Option(
+        False,
+        "--json",
+        envvar="CONFGUARD_JSON",
+        help="Report errors as JSON object on stderr",
+    ),
 ):
+    no_color = no_color or os.environ.get("NO_COLOR", "") != ""
+    if no_color:
+        ctx.color = False  # inherited by the subcommand context, honoured by secho
     # log_fmt = r"%(asctime)-15s %(levelname)-7s %(message)s"
     log_fmt = r"%(message)s"
     # https://github.com/Textualize/rich/issues/1161#issuecomment-813882224
@@ -218,6 +728,8 @@ def main(
             }
         ),
         highlight=False,
+        stderr=True,  # keep stdout for command output
+        no_color=no_color,
     )
     if verbose:
         logging.basicConfig(
@@ -233,6 +745,25 @@ def main(
             datefmt="%m-%d %H:%M:%S",
             handlers=[RichHandler(show_time=False, show_path=False, console=console)],
         )
+    if trace_file is not None:
+        _add_trace_handler(trace_file)
+    if read_only and ctx.invoked_subcommand not in (None, *READ_ONLY_COMMANDS):
+        _fail(f"Read-only mode, refusing to run {ctx.invoked_subcommand}.", "read_only")
+
+
+def _add_trace_handler(trace_file: Path) -> None:
+    """Debug logs go to `trace_file`, the console handler keeps its own level"""
+    root = logging.getLogger()
+    for handler in root.handlers:
+        if handler.level == logging.NOTSET:
+            handler.setLevel(root.level)
+    file_handler = logging.FileHandler(trace_file, encoding="utf-8")
+    file_handler.setLevel(logging.DEBUG)
+    file_handler.setFormatter(
+        logging.Formatter(r"%(asctime)s %(levelname)-7s %(name)s %(message)s")
+    )
+    root.addHandler(file_handler)
+    root.setLevel(logging.DEBUG)
 
 
 if __name__ == "__main__":
@@ -1,13 +1,33 @@
+import hashlib
 import logging
+import shlex
 import shutil
 import uuid
 from dataclasses import dataclass, field
+from datetime import datetime, timezone
 from pathlib import Path
 from typing import Optional
 
-from confguard.environment import CONFGUARD_BKP_DIR, CONFGUARD_CONFIG_FILE, config
-from confguard.exceptions import BackupExistError, DirectoryNotDeleted
-from confguard.helper import _create_relative_path
+import tomlkit
+from tomlkit.exceptions import TOMLKitError
+
+from confguard.environment import (
+    CONFGUARD_BKP_DIR,
+    CONFGUARD_CONFIG_FILE,
+    COPY_STUB_HEADER,
+    config,
+)
+from confguard.exceptions import (
+    BackupExistError,
+    DirectoryNotDeleted,
+    InvalidConfigError,
+    ProtectedPathError,
+    SentinelExistsError,
+    SentinelMismatchError,
+    SourceDirNotWritableError,
+    TargetOutsideBaseError,
+)
+from confguard.helper import _create_relative_path, _is_protected, _is_writable
 
 _log = logging.getLogger(__name__)
 
@@ -21,6 +41,8 @@ class ConfGuard:
     config_path: Path = field(init=False)
     sentinel: Optional[str] = None
     is_relative: bool = False
+    is_copy: bool = False  # copy mode: regular stub files instead of symlinks
+    checksums: Optional[dict[str, str]] = None  # recorded at guard time
 
     # files: Files
     # links: Links
@@ -33,13 +55,53 @@ class ConfGuard:
             _log.debug(f"Sentinel already exists: {self.sentinel=}")
             return
 
-        self.sentinel = f"{self.source_dir.name}-{uuid.uuid4().hex[:8]}"
-        self.target_dir = config.confguard_path / self.sentinel
+        if config.confguard_sentinel_by_path:  # same path, same sentinel
+            suffix = hashlib.sha256(str(self.source_dir).encode("utf-8")).hexdigest()
+        else:
+            suffix = uuid.uuid4().hex
+        sentinel = f"{self.source_dir.name}-{suffix[:8]}"
+        target_dir = config.confguard_path / sentinel
+        if target_dir.exists() or target_dir.is_symlink():
+            raise SentinelExistsError(
+                f"Sentinel {target_dir} already exists, "
+                f"use find-and-link to restore its project or remove it."
+            )
+        self.sentinel = sentinel
+        self.target_dir = target_dir
         _log.debug(f"Sentinel created: {self.sentinel=}")
 
     def remove_sentinel(self) -> None:
         self.sentinel = None
 
+    def check_writable(self) -> None:
+        """Refuse to guard if the files cannot be moved out of the project"""
+        dirs = {(self.source_dir / t).parent for t in self.targets}
+        for dir_ in sorted(d for d in dirs | {self.source_dir} if d.is_dir()):
+            if not _is_writable(dir_):
+                raise SourceDirNotWritableError(f"{dir_} is not writable.")
+
+    def check_protected(self) -> None:
+        """Refuse to guard a directory in CONFGUARD_PROTECTED_PATHS"""
+        if _is_protected(self.source_dir, config.confguard_protected_paths):
+            raise ProtectedPathError(f"{self.source_dir} is a protected path.")
+
+    def check_copy_targets(self) -> None:
+        """Copy mode writes `source_env` stubs, which only direnv files can use"""
+        if not self.is_copy:
+            return
+        invalid = [t for t in self.targets if Path(t).name != ".envrc"]
+        if len(invalid) > 0:
+            raise InvalidConfigError(
+                f"Copy mode only supports .envrc targets, not {invalid}."
+            )
+
+    def check_target_dir(self) -> None:
+        """Refuse a target_dir outside of CONFGUARD_PATH, e.g. from an edited sentinel"""
+        base = Path(config.confguard_path).resolve()
+        target = Path(self.target_dir).resolve()
+        if base not in target.parents:
+            raise TargetOutsideBaseError(f"{target} is not inside {base}.")
+
     @staticmethod
     def _move_files(source_dir: Path, target_dir: Path, targets: list[str]) -> None:
         for rel_path in targets:
@@ -47,6 +109,8 @@ class ConfGuard:
             src_path = source_dir / rel_path
 
             if src_path.exists():
+                if tgt_path.exists() or tgt_path.is_symlink():  # rename replaces it
+                    raise FileExistsError(f"{tgt_path} already exists.")
                 _log.debug(f"Moving {src_path} to {tgt_path}")
                 tgt_path.parent.exists() or tgt_path.parent.mkdir(parents=True)
                 src_path.rename(tgt_path)
@@ -58,8 +122,49 @@ class ConfGuard:
         Path(self.target_dir).mkdir(parents=True, exist_ok=True)
         self._move_files(self.source_dir, self.target_dir, targets=self.targets)
 
+    def chmod_files(self, mode: int) -> None:
+        """Sets mode of guarded files, directories keep their mode"""
+        for rel_path in self.targets:
+            tgt_path = self.target_dir / rel_path
+            paths = tgt_path.rglob("*") if tgt_path.is_dir() else [tgt_path]
+            for p in paths:
+                if p.is_file() and not p.is_symlink():
+                    _log.debug(f"Setting mode {mode:o} on {p}")
+                    p.chmod(mode)
+
+    def compute_checksums(self, targets: list[str]) -> dict[str, str]:
+        """SHA-256 of every guarded file in the sentinel, keyed by its relative path"""
+        checksums = {}
+        for rel_path in targets:
+            tgt_path = self.target_dir / rel_path
+            paths = sorted(tgt_path.rglob("*")) if tgt_path.is_dir() else [tgt_path]
+            for p in paths:
+                if p.is_file() and not p.is_symlink():
+                    rel = str(p.relative_to(self.target_dir))
+                    checksums[rel] = hashlib.sha256(p.read_bytes()).hexdigest()
+        return checksums
+
+    def verify_checksums(self) -> dict[str, str]:
+        """Compares the sentinel with the checksums recorded at guard time
+        ok: unchanged, MODIFIED: content changed, MISSING: deleted, NEW: not recorded
+        """
+        assert self.checksums is not None, "No checksums recorded"
+        current = self.compute_checksums(self.files)
+        status = {}
+        for rel_path, checksum in self.checksums.items():
+            if rel_path not in current:
+                status[rel_path] = "MISSING"
+            elif current[rel_path] != checksum:
+                status[rel_path] = "MODIFIED"
+            else:
+                status[rel_path] = "ok"
+        for rel_path in current.keys() - self.checksums.keys():
+            status[rel_path] = "NEW"
+        return dict(sorted(status.items()))
+
     def unmove_files(self) -> None:
         """Restore files from confguard directory, based on saved file list"""
+        self.check_target_dir()
         self._move_files(self.target_dir, self.source_dir, self.files)
         shutil.rmtree(self.target_dir)
 
@@ -152,10 +257,44 @@ class ConfGuard:
             if self.is_relative:
                 tgt_path = _create_relative_path(str(src_path), str(tgt_path))
 
+            if self.is_copy:
+                _log.debug(f"Creating stub {src_path} for {tgt_path}")
+                stub = f"{COPY_STUB_HEADER}\nsource_env {shlex.quote(str(tgt_path))}\n"
+                with open(src_path, mode="xt", encoding="utf-8") as fp:
+                    fp.write(stub)
+                continue
+
             _log.debug(f"Creating link {src_path} to {tgt_path}")
             src_path.symlink_to(tgt_path)
             _ = None
 
+    @staticmethod
+    def _stub_target(src_path: Path) -> Optional[Path]:
+        """Guarded file sourced by a copy-mode stub, None if `src_path` is no stub"""
+        if src_path.is_symlink() or not src_path.is_file():
+            return None
+        try:
+            with open(src_path, mode="rt", encoding="utf-8") as fp:
+                lines = fp.read(4096).splitlines()
+        except (OSError, UnicodeDecodeError):
+            return None
+        if len(lines) < 2 or lines[0] != COPY_STUB_HEADER:
+            return None
+        command = shlex.split(lines[1])
+        if len(command) != 2 or command[0] != "source_env":
+            return None
+        return src_path.parent / command[1]  # relative stubs are relative to the stub
+
+    def linked_to(self, rel_path: str) -> Optional[Path]:
+        """Resolved target of the link or stub at `rel_path` in the project"""
+        src_path = self.source_dir / rel_path
+        if src_path.is_symlink():
+            return src_path.resolve()
+        if self.is_copy:
+            target = self._stub_target(src_path)
+            return None if target is None else target.resolve()
+        return None
+
     def remove_lk(self, targets: list[str]) -> None:
         for rel_path in targets:
             src_path = self.source_dir / rel_path
@@ -163,6 +302,9 @@ class ConfGuard:
             if src_path.is_symlink():
                 _log.debug(f"Removing link {src_path}")
                 src_path.unlink(missing_ok=True)
+            elif self.is_copy and self._stub_target(src_path) is not None:
+                _log.debug(f"Removing stub {src_path}")
+                src_path.unlink(missing_ok=True)
             else:
                 _log.info(
                     f"File {str(src_path)} is not a symlink. Skipping removal.",
@@ -184,6 +326,57 @@ class ConfGuard:
         _log.debug(f"Removing link {source}")
         source.unlink(missing_ok=True)
 
+    def broken_links(self) -> list[str]:
+        """Guarded files which are linked in the project but missing in the sentinel"""
+        return [
+            rel_path
+            for rel_path in self.files
+            if self.linked_to(rel_path) is not None
+            and not (self.target_dir / rel_path).exists()
+        ]
+
+    def conflicts(self) -> list[str]:
+        """Guarded files whose place in the project is taken by a regular file"""
+        return [
+            rel_path
+            for rel_path in self.files
+            if (self.target_dir / rel_path).exists()
+            and (self.source_dir / rel_path).exists()
+            and self.linked_to(rel_path) is None
+        ]
+
+    def file_status(self) -> dict[str, str]:
+        """Link status of every file in the sentinel
+        ok: linked correctly, no link / wrong link: source side is broken,
+        not in sentinel: guarded file is missing, orphan: not a guarded file
+        """
+        status = {}
+        for rel_path in self.files:
+            tgt_path = self.target_dir / rel_path
+            linked_to = self.linked_to(rel_path)
+            if not tgt_path.exists():
+                status[rel_path] = "not in sentinel"
+            elif linked_to is None:
+                status[rel_path] = "no link"
+            elif linked_to != tgt_path.resolve():
+                status[rel_path] = "wrong link"
+            else:
+                status[rel_path] = "ok"
+
+        internal = {
+            f".{self.sentinel}.confguard",
+            Path(CONFGUARD_CONFIG_FILE).with_suffix(".bkp").name,
+        }
+        guarded = [Path(f) for f in self.files]
+        for p in sorted(self.target_dir.rglob("*")):
+            rel = p.relative_to(self.target_dir)
+            if p.is_dir() or str(rel) in internal or rel.parts[0] == CONFGUARD_BKP_DIR:
+                continue
+            if any(rel == g or g in rel.parents for g in guarded):
+                continue
+            status[str(rel)] = "orphan"
+        return status
+
     def backup_toml(self) -> None:
         """Backup toml file
         IMPORTANT: ensure that the relevant state is saved in the toml file before backing up.
@@ -192,10 +385,35 @@ class ConfGuard:
         toml_bkp = (self.target_dir / CONFGUARD_CONFIG_FILE).with_suffix(".bkp")
         shutil.copy2(toml, toml_bkp)
 
+    def guarded_at(self) -> Optional[datetime]:
+        """Guard timestamp as recorded in the toml backup of the sentinel"""
+        toml_bkp = (self.target_dir / CONFGUARD_CONFIG_FILE).with_suffix(".bkp")
+        try:
+            with open(toml_bkp, mode="rt", encoding="utf-8") as fp:
+                timestamp = tomlkit.load(fp).get("_internal_", {}).get("timestamp")
+        except (OSError, UnicodeDecodeError, TOMLKitError, AttributeError) as e:
+            _log.debug(f"No timestamp in {toml_bkp}: {e!r}")
+            return None
+        if not isinstance(timestamp, datetime):  # e.g. date only
+            return None
+        if timestamp.tzinfo is not None:  # comparable with naive UTC timestamps
+            timestamp = timestamp.astimezone(timezone.utc).replace(tzinfo=None)
+        return timestamp
+
     @staticmethod
     def restore_toml(source_dir: Path, target_dir: Path) -> Path:
+        """Restore toml file from its backup
+        The backup must record `target_dir` as its sentinel,
+        otherwise a foreign project would be linked.
+        """
         toml = source_dir / CONFGUARD_CONFIG_FILE
         toml_bkp = (target_dir / CONFGUARD_CONFIG_FILE).with_suffix(".bkp")
+        with open(toml_bkp, mode="rt", encoding="utf-8") as fp:
+            sentinel = tomlkit.load(fp).get("_internal_", {}).get("sentinel")
+        if sentinel != target_dir.name:
+            raise SentinelMismatchError(
+                f"{toml_bkp} records sentinel {sentinel}, expected {target_dir.name}."
+            )
         shutil.copy2(toml_bkp, toml)
         _log.info(f"Restored configuration file: {toml}")
         return toml
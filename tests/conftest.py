@@ -1,10 +1,15 @@
 import logging
+import os
 import shutil
+import tempfile
 from pathlib import Path
 
 import pytest
 from tomlkit import table
 
+# never use the real store: it defaults to the XDG data directory and is read on import
+os.environ["CONFGUARD_PATH"] = tempfile.mkdtemp(prefix="confguard-test-")
+
 from confguard.environment import CONFGUARD_BKP_DIR, ROOT_DIR, config
 
 _log = logging.getLogger(__name__)
@@ -20,6 +25,10 @@ REF_PROJ = ROOT_DIR / "tests/resources/ref_proj"
 # run fixture before all tests
 @pytest.fixture(autouse=True)
 def test_proj():
+    store = Path(config.confguard_path).resolve()
+    assert store.is_relative_to(
+        Path(tempfile.gettempdir()).resolve()
+    ), f"Refusing to delete {store}, not a temporary directory"
     shutil.rmtree(config.confguard_path, ignore_errors=True)
     Path(config.confguard_path).mkdir(parents=True, exist_ok=True)
 
@@ -1,5 +1,8 @@
+import pytest
+
 from confguard.adapter import TomlRepoConfGuard
-from confguard.environment import CONFGUARD_CONFIG_FILE
+from confguard.environment import CONFGUARD_CONFIG_FILE, config
+from confguard.exceptions import InvalidConfigError
 from confguard.model import ConfGuard
 from tests.conftest import REF_PROJ, SENTINEL, TEST_PROJ
 
@@ -19,6 +22,33 @@ class TestTomlRepoConfGuard:
         assert isinstance(cg, ConfGuard)
         assert cg.files == [".envrc", ".run", "xxx/xxx.txt"]
 
+    def test_get_relative_default(self, monkeypatch):
+        # given: .confguard without `relative`
+        path = TEST_PROJ / CONFGUARD_CONFIG_FILE
+        path.write_text(path.read_text().replace("relative = false\n", ""))
+        monkeypatch.setattr(config, "confguard_relative", True)
+
+        cg = TomlRepoConfGuard(source_dir=TEST_PROJ).get()
+        assert cg.is_relative
+
+    def test_get_with_crlf_line_endings(self):
+        content = (TEST_PROJ / ".." / CONFGUARD_CONFIG_FILE).read_text()
+        (TEST_PROJ / CONFGUARD_CONFIG_FILE).write_bytes(
+            content.replace("\n", "\r\n").encode("utf-8")
+        )
+        repo = TomlRepoConfGuard(source_dir=TEST_PROJ)
+        cg = repo.get()
+
+        assert cg.sentinel == "test_proj-947dcae6"
+        assert cg.targets == [".envrc", ".run", "xxx/xxx.txt"]
+        assert cg.files == [".envrc", ".run", "xxx/xxx.txt"]
+
+    def test_invalid_utf8(self):
+        content = (TEST_PROJ / CONFGUARD_CONFIG_FILE).read_bytes()
+        (TEST_PROJ / CONFGUARD_CONFIG_FILE).write_bytes(content + b"# caf\xe9\n")
+        with pytest.raises(InvalidConfigError, match="line 6"):
+            TomlRepoConfGuard(source_dir=TEST_PROJ)
+
     def test_add_without_change(self):
         repo = TomlRepoConfGuard(source_dir=TEST_PROJ)
         cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc", ".run", "xxx/xxx.txt"])
@@ -37,6 +67,52 @@ class TestTomlRepoConfGuard:
         assert under_test != ref
         assert "[_internal_] # DO NOT EDIT FROM HERE" in under_test
 
+    def test_add_new_with_source_date_epoch(self, monkeypatch):
+        monkeypatch.setenv("SOURCE_DATE_EPOCH", "1672531200")
+        repo = TomlRepoConfGuard(source_dir=TEST_PROJ)
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc", ".run", "xxx/xxx.txt"])
+        cg.create_sentinel()
+        repo.add(cg)
+        under_test = (TEST_PROJ / CONFGUARD_CONFIG_FILE).read_text()
+        assert "timestamp = 2023-01-01T00:00:00\n" in under_test
+
+    def test_add_checksums(self):
+        repo = TomlRepoConfGuard(source_dir=TEST_PROJ)
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc", ".run", "xxx/xxx.txt"])
+        cg.create_sentinel()
+        cg.checksums = {".envrc": "ab12", ".run/app.run.xml": "cd34"}
+        repo.add(cg)
+
+        under_test = (TEST_PROJ / CONFGUARD_CONFIG_FILE).read_text()
+        assert '".run/app.run.xml" = "cd34"' in under_test
+        assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().checksums == cg.checksums
+
+    @pytest.mark.parametrize("epoch", ("abc", "-1", "1.5", "99999999999999999999"))
+    def test_add_new_with_invalid_source_date_epoch(self, monkeypatch, epoch):
+        monkeypatch.setenv("SOURCE_DATE_EPOCH", epoch)
+        repo = TomlRepoConfGuard(source_dir=TEST_PROJ)
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc", ".run", "xxx/xxx.txt"])
+        cg.create_sentinel()
+        with pytest.raises(InvalidConfigError, match="SOURCE_DATE_EPOCH"):
+            repo.add(cg)
+
+    def test_add_new_with_empty_source_date_epoch(self, monkeypatch):
+        monkeypatch.setenv("SOURCE_DATE_EPOCH", "")
+        repo = TomlRepoConfGuard(source_dir=TEST_PROJ)
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc", ".run", "xxx/xxx.txt"])
+        cg.create_sentinel()
+        repo.add(cg)
+        assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel == cg.sentinel
+
+    def test_add_relative(self):
+        repo = TomlRepoConfGuard(source_dir=TEST_PROJ)
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc"], is_relative=True)
+        cg.create_sentinel()
+        repo.add(cg)
+
+        # then: the saved link style wins over `relative = false` in the config
+        assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().is_relative
+
     def test_add_update(self):
         repo = TomlRepoConfGuard(source_dir=TEST_PROJ)
         cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc", ".run", "xxx/xxx.txt"])
@@ -1,14 +1,34 @@
+import json
+import logging
+import os
+import shutil
+import stat
 from pathlib import Path
 
 import pytest
 import tomlkit
+import typer
 from click.exceptions import Exit
 from typer.testing import CliRunner
 
-from confguard.environment import CONFGUARD_CONFIG_FILE, config
-from confguard.main import _find_and_link, _guard, _unguard, app
+import confguard.model
+from confguard.adapter import TomlRepoConfGuard
+from confguard.environment import CONFGUARD_BKP_DIR, CONFGUARD_CONFIG_FILE, config
+from confguard.formatter import SortKey
+from confguard.main import (
+    READ_ONLY_COMMANDS,
+    _find_and_link,
+    _guard,
+    _list_guarded,
+    _relink_all,
+    _sort_guarded,
+    _unguard,
+    _unguard_all,
+    _verify_guarded,
+    app,
+)
 from confguard.model import ConfGuard
-from tests.conftest import TEST_PROJ
+from tests.conftest import REF_PROJ, TEST_PROJ
 
 runner = CliRunner()
 
@@ -23,6 +43,150 @@ class TestGuard:
         assert result.exit_code == 0
         # assert Path(config.confguard_path).exists()
 
+    def test_guard_print_path(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        result = runner.invoke(app, ["guard", "--print-path", str(TEST_PROJ)])
+        assert result.exit_code == 0
+        sentinel_dir = Path(result.stdout.strip())
+        assert sentinel_dir.parent == config.confguard_path
+        assert (TEST_PROJ / ".envrc").resolve() == sentinel_dir / ".envrc"
+
+        # when guarded again, only the path is printed
+        result = runner.invoke(app, ["guard", "--print-path", str(TEST_PROJ)])
+        assert result.exit_code == 0
+        assert result.stdout.strip() == str(sentinel_dir)
+
+    @pytest.mark.parametrize("flag", ("--relative", "--absolute"))
+    def test_guard_link_style(self, caplog, flag):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        result = runner.invoke(app, ["guard", flag, str(TEST_PROJ)])
+        assert result.exit_code == 0
+        link = Path(os.readlink(TEST_PROJ / ".envrc"))
+        assert link.is_absolute() == (flag == "--absolute")
+
+    def test_guard_chmod(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        result = runner.invoke(
+            app, ["guard", "--chmod", "600", "--print-path", str(TEST_PROJ)]
+        )
+        assert result.exit_code == 0
+        sentinel_dir = Path(result.stdout.strip())
+        for f in (".envrc", "xxx/xxx.txt", ".run/app.run.xml"):
+            assert stat.S_IMODE((sentinel_dir / f).stat().st_mode) == 0o600
+        assert stat.S_IMODE((sentinel_dir / ".run").stat().st_mode) != 0o600
+
+    @pytest.mark.parametrize("mode", ("rw", "9", "17777"))
+    def test_guard_chmod_invalid(self, caplog, mode):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        result = runner.invoke(app, ["guard", "--chmod", mode, str(TEST_PROJ)])
+        assert result.exit_code == 2
+        assert not (TEST_PROJ / ".envrc").is_symlink()
+
+    def test_guard_init(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        (TEST_PROJ / CONFGUARD_CONFIG_FILE).unlink()
+        result = runner.invoke(app, ["guard", str(TEST_PROJ)])
+        assert result.exit_code == 3
+        assert not (TEST_PROJ / CONFGUARD_CONFIG_FILE).exists()
+
+        result = runner.invoke(app, ["guard", "--init", str(TEST_PROJ)])
+        assert result.exit_code == 0
+        cg = TomlRepoConfGuard(source_dir=TEST_PROJ).get()
+        assert cg.targets == [".envrc", ".run"]
+        assert (TEST_PROJ / ".envrc").is_symlink()
+        assert (TEST_PROJ / ".run").is_symlink()
+
+    def test_guard_init_refused(self, caplog, monkeypatch):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        (TEST_PROJ / CONFGUARD_CONFIG_FILE).unlink()
+        monkeypatch.setattr(config, "confguard_protected_paths", [TEST_PROJ])
+        result = runner.invoke(app, ["guard", "--init", str(TEST_PROJ)])
+        assert result.exit_code == 6
+        assert not (TEST_PROJ / CONFGUARD_CONFIG_FILE).exists()
+
+    def test_guard_init_strict_worktree(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        (TEST_PROJ / CONFGUARD_CONFIG_FILE).unlink()
+        (TEST_PROJ / ".git").write_text("gitdir: /repo/.git/worktrees/test_proj\n")
+        try:
+            result = runner.invoke(app, ["guard", "--init", "--strict", str(TEST_PROJ)])
+        finally:
+            (TEST_PROJ / ".git").unlink()
+        assert result.exit_code == 6
+        assert not (TEST_PROJ / CONFGUARD_CONFIG_FILE).exists()
+
+    def test_guard_verify_after(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        result = runner.invoke(app, ["guard", "--verify-after", str(TEST_PROJ)])
+        assert result.exit_code == 0
+        assert (TEST_PROJ / ".envrc").is_symlink()
+
+    def test_guard_verify_after_rolls_back(self, caplog, monkeypatch):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        monkeypatch.setattr(ConfGuard, "back_create", lambda self: None)
+        result = runner.invoke(app, ["guard", "--verify-after", str(TEST_PROJ)])
+        assert result.exit_code == 1
+        assert "no link back to project" in result.output
+        assert (TEST_PROJ / ".envrc").is_file()
+        assert not (TEST_PROJ / ".envrc").is_symlink()
+        assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel is None
+        assert list(config.confguard_path.glob("*")) == []
+
+    def test_guard_verify_after_rolls_back_unsaved(self, caplog, monkeypatch):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        monkeypatch.setattr(TomlRepoConfGuard, "add", lambda self, cg: None)
+        result = runner.invoke(app, ["guard", "--verify-after", str(TEST_PROJ)])
+        assert result.exit_code == 1
+        assert "sentinel not saved" in result.output
+        assert "rolled back" in result.output
+        for t in (".envrc", ".run", "xxx/xxx.txt"):
+            assert (TEST_PROJ / t).exists()
+            assert not (TEST_PROJ / t).is_symlink()
+        assert (TEST_PROJ / ".run/app.run.xml").is_file()
+        assert list(config.confguard_path.glob("*")) == []
+
+    def test_guard_linked_worktree_strict(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        (TEST_PROJ / ".git").write_text("gitdir: /repo/.git/worktrees/test_proj\n")
+        try:
+            result = runner.invoke(app, ["guard", "--strict", str(TEST_PROJ)])
+        finally:
+            (TEST_PROJ / ".git").unlink()
+        assert result.exit_code == 6
+        assert not (TEST_PROJ / ".envrc").is_symlink()
+
+    def test_guard_invalid_utf8(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        with open(TEST_PROJ / CONFGUARD_CONFIG_FILE, mode="ab") as fp:
+            fp.write(b"# caf\xe9\n")
+        result = runner.invoke(app, ["guard", str(TEST_PROJ)])
+        assert result.exit_code == 1
+        assert isinstance(result.exception, SystemExit)
+
     def test_unguard(self, caplog):
         # given guarded project
         caplog.set_level(
@@ -35,6 +199,169 @@ class TestGuard:
         print(result.stdout)
         assert result.exit_code == 0
 
+    def test_verify(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        cg = _guard(source_dir=TEST_PROJ)
+        result = runner.invoke(app, ["verify", "--format", "json", str(TEST_PROJ)])
+        assert result.exit_code == 0
+        assert {"file": ".run/app.run.xml", "status": "ok"} in json.loads(
+            result.stdout
+        )
+
+        (cg.target_dir / ".envrc").write_text("export FOO=tampered\n")
+        (cg.target_dir / "xxx/xxx.txt").unlink()
+        (cg.target_dir / ".run/new.xml").write_text("<component/>\n")
+        result = runner.invoke(app, ["verify", "--format", "json", str(TEST_PROJ)])
+        assert result.exit_code == 1
+        status = {r["file"]: r["status"] for r in json.loads(result.stdout)}
+        assert status == {
+            ".envrc": "MODIFIED",
+            ".run/app.run.xml": "ok",
+            ".run/new.xml": "NEW",
+            "xxx/xxx.txt": "MISSING",
+        }
+
+    def test_verify_fix(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        cg = _guard(source_dir=TEST_PROJ)
+        (TEST_PROJ / ".envrc").unlink()
+        (TEST_PROJ / ".run").unlink()
+
+        result = runner.invoke(app, ["verify", "--fix", str(TEST_PROJ)])
+        assert result.exit_code == 0
+        assert (TEST_PROJ / ".envrc").resolve() == cg.target_dir / ".envrc"
+        assert (TEST_PROJ / ".run").resolve() == cg.target_dir / ".run"
+
+    def test_verify_fix_conflict(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        _guard(source_dir=TEST_PROJ)
+        (TEST_PROJ / ".envrc").unlink()
+        (TEST_PROJ / ".envrc").write_text("export FOO=local\n")
+
+        result = runner.invoke(app, ["verify", "--fix", str(TEST_PROJ)])
+        assert result.exit_code == 1
+        assert "Not a link, resolve manually" in result.output
+        assert not (TEST_PROJ / ".envrc").is_symlink()
+
+    def test_verify_without_checksums(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        # given a project guarded before checksums were recorded
+        _guard(source_dir=TEST_PROJ)
+        path = TEST_PROJ / CONFGUARD_CONFIG_FILE
+        toml = tomlkit.parse(path.read_text())
+        del toml["_internal_"]["checksums"]
+        path.write_text(tomlkit.dumps(toml))
+
+        result = runner.invoke(app, ["verify", str(TEST_PROJ)])
+        assert result.exit_code == 1
+        assert "No checksums recorded" in result.output
+
+    def test_unguard_all(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        _guard(source_dir=TEST_PROJ)
+        result = runner.invoke(app, ["unguard-all"])
+        assert result.exit_code == 0
+        assert not (TEST_PROJ / ".envrc").is_symlink()
+
+    def test_show(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        _guard(source_dir=TEST_PROJ)
+        result = runner.invoke(app, ["show", "--format", "json", str(TEST_PROJ)])
+        assert result.exit_code == 0
+        assert {"file": ".envrc", "status": "ok"} in json.loads(result.stdout)
+
+    def test_show_not_guarded(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        result = runner.invoke(app, ["show", str(TEST_PROJ)])
+        assert result.exit_code == 5
+
+    def test_which(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        cg = _guard(source_dir=TEST_PROJ)
+        result = runner.invoke(app, ["which", str(TEST_PROJ)])
+        assert result.exit_code == 0
+        assert result.stdout.splitlines() == [cg.sentinel, str(cg.target_dir)]
+
+    def test_which_not_guarded(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        result = runner.invoke(app, ["which", str(TEST_PROJ)])
+        assert result.exit_code == 5
+
+    @pytest.mark.parametrize("command", ("show", "unguard", "verify", "which"))
+    def test_config_not_found(self, caplog, command):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        (TEST_PROJ / CONFGUARD_CONFIG_FILE).unlink()
+        result = runner.invoke(app, [command, str(TEST_PROJ)])
+        assert result.exit_code == 3
+        assert "not found" in result.output
+
+    @pytest.mark.parametrize(
+        ("args", "env", "colored"),
+        (
+            ([], {}, True),
+            (["--no-color"], {}, False),
+            ([], {"NO_COLOR": "1"}, False),
+        ),
+    )
+    def test_no_color(self, caplog, args, env, colored):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        result = runner.invoke(app, [*args, "list"], env=env, color=True)
+        assert result.exit_code == 0
+        assert ("\x1b[" in result.stdout) == colored
+
+    def test_list(self, caplog):
+        # given guarded project
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        cg = _guard(source_dir=TEST_PROJ)
+        # when
+        result = runner.invoke(app, ["list"])
+        # then
+        print(result.stdout)
+        assert result.exit_code == 0
+        assert cg.sentinel in result.stdout
+        assert str(TEST_PROJ) in result.stdout
+        assert "1 guarded project(s)" in result.stdout
+
+    def test_list_json(self, caplog):
+        caplog.set_level(
+            100000
+        )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+        cg = _guard(source_dir=TEST_PROJ)
+        result = runner.invoke(app, ["list", "--format", "json"])
+        assert result.exit_code == 0
+        rows = json.loads(result.stdout)
+        assert rows == [
+            {
+                "sentinel": cg.sentinel,
+                "source_dir": str(TEST_PROJ),
+                "target_dir": str(cg.target_dir),
+            }
+        ]
+
 
 def test__guard():
     cg = _guard(source_dir=TEST_PROJ)
@@ -84,6 +411,532 @@ def test__unguard():
     assert cg.sentinel is None
 
 
+def test__unguard_with_sentinel_outside_confguard_path():
+    # given a guarded project whose sentinel has been edited to escape CONFGUARD_PATH
+    _ = _guard(source_dir=TEST_PROJ)
+    outside = config.confguard_path.parent / "outside"
+    outside.mkdir(exist_ok=True)
+    path = TEST_PROJ / CONFGUARD_CONFIG_FILE
+    with open(path, mode="rt", encoding="utf-8") as fp:
+        toml = tomlkit.load(fp)
+    toml["_internal_"]["sentinel"] = "../outside"
+    with open(path, mode="wt", encoding="utf-8") as fp:
+        tomlkit.dump(toml, fp)
+
+    # when
+    with pytest.raises(Exit):
+        _unguard(source_dir=TEST_PROJ)
+
+    # then nothing outside CONFGUARD_PATH has been touched
+    assert outside.is_dir()
+    assert (TEST_PROJ / ".envrc").is_symlink()
+    outside.rmdir()
+
+
+@pytest.mark.parametrize(
+    ("args", "env"), ((["--json"], {}), ([], {"CONFGUARD_JSON": "1"}))
+)
+def test_json_errors(caplog, args, env):
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    result = runner.invoke(app, [*args, "verify", str(TEST_PROJ)], env=env)
+    assert result.exit_code == 5
+    error = json.loads(result.output.splitlines()[-1])["error"]
+    assert error == {
+        "code": "not_guarded",
+        "message": f"Project {TEST_PROJ} is not guarded.",
+    }
+
+
+def test_json_errors_from_exception(caplog):
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    (TEST_PROJ / CONFGUARD_CONFIG_FILE).write_bytes(b"[config]\ntargets = ['\xff']\n")
+    result = runner.invoke(app, ["--json", "guard", str(TEST_PROJ)])
+    assert result.exit_code == 1
+    assert json.loads(result.output)["error"]["code"] == "invalid_config"
+
+
+def test_guard_backup_exists(caplog):
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    # given a backup left by an interrupted run
+    (TEST_PROJ / CONFGUARD_BKP_DIR).mkdir()
+    (TEST_PROJ / CONFGUARD_BKP_DIR / ".envrc").write_text("export FOO=bar\n")
+
+    result = runner.invoke(app, ["guard", str(TEST_PROJ)])
+
+    assert result.exit_code == 4
+    assert (TEST_PROJ / CONFGUARD_BKP_DIR / ".envrc").is_file()
+    assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel is None
+
+
+def test__unguard_all():
+    # given a guarded project and a sentinel whose project has vanished
+    cg = _guard(source_dir=TEST_PROJ)
+    orphan = config.confguard_path / "orphan-12345678"
+    orphan.mkdir()
+    (orphan / ".orphan-12345678.confguard").symlink_to(orphan / "does-not-exist")
+
+    # when
+    failed = _unguard_all()
+
+    # then the project is un-guarded and only the orphan is reported
+    assert failed == [orphan / "does-not-exist"]
+    assert not cg.target_dir.exists()
+    assert (TEST_PROJ / ".envrc").is_file()
+    assert (TEST_PROJ / ".run").is_dir()
+
+
+def test_file_status():
+    # given a guarded project with a removed link and an orphaned sentinel file
+    cg = _guard(source_dir=TEST_PROJ)
+    cg = TomlRepoConfGuard(source_dir=TEST_PROJ).get()
+    (TEST_PROJ / ".envrc").unlink()
+    (cg.target_dir / "xxx/orphan.txt").write_text("orphan")
+
+    # when
+    status = cg.file_status()
+
+    # then
+    assert status == {
+        ".envrc": "no link",
+        ".run": "ok",
+        "xxx/xxx.txt": "ok",
+        "xxx/orphan.txt": "orphan",
+    }
+
+
+@pytest.mark.parametrize("step", ("move_files", "create_lk", "back_create"))
+def test__guard_interrupted_is_rolled_back(monkeypatch, step):
+    # given guarding is interrupted by Ctrl-C in the critical section
+    def interrupt(*args, **kwargs):
+        raise KeyboardInterrupt
+
+    monkeypatch.setattr(ConfGuard, step, interrupt)
+
+    # when
+    with pytest.raises(Exit):
+        _guard(source_dir=TEST_PROJ)
+
+    # then the project is restored and not recorded as guarded
+    assert (TEST_PROJ / ".envrc").is_file()
+    assert not (TEST_PROJ / ".envrc").is_symlink()
+    assert (TEST_PROJ / "xxx/xxx.txt").is_file()
+    assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel is None
+
+
+@pytest.mark.parametrize(
+    ("command", "step", "code"),
+    (
+        ("guard", "move_files", "guard_failed"),
+        ("unguard", "unmove_files", "unguard_failed"),
+    ),
+)
+def test_interrupted_is_reported(caplog, monkeypatch, command, step, code):
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    if command == "unguard":
+        _guard(source_dir=TEST_PROJ)
+
+    def fail(*args, **kwargs):
+        raise OSError("disk full")
+
+    monkeypatch.setattr(ConfGuard, step, fail)
+
+    result = runner.invoke(app, ["--json", command, str(TEST_PROJ)])
+    assert result.exit_code == 1
+    error = json.loads(result.output.splitlines()[-1])["error"]
+    assert error == {"code": code, "message": "Error occurred, rolled back: disk full"}
+    assert (TEST_PROJ / ".envrc").is_symlink() == (command == "unguard")
+
+
+def test__relink_all():
+    # given a guarded project restored without its links and .confguard
+    cg = _guard(source_dir=TEST_PROJ)
+    (TEST_PROJ / CONFGUARD_CONFIG_FILE).unlink()
+    (TEST_PROJ / ".envrc").unlink()
+    (TEST_PROJ / "xxx/xxx.txt").unlink()
+    (TEST_PROJ / "xxx/xxx.txt").symlink_to("/does/not/exist")
+
+    # when
+    results = _relink_all()
+
+    # then the links point into the sentinel again
+    assert results == {TEST_PROJ: "relinked"}
+    assert (TEST_PROJ / CONFGUARD_CONFIG_FILE).exists()
+    assert (TEST_PROJ / ".envrc").resolve() == cg.target_dir / ".envrc"
+    assert (TEST_PROJ / "xxx/xxx.txt").resolve() == cg.target_dir / "xxx/xxx.txt"
+    assert (TEST_PROJ / ".run").resolve() == cg.target_dir / ".run"
+
+    # and a second run leaves everything untouched
+    assert _relink_all() == {TEST_PROJ: "skipped"}
+
+
+def test__relink_all_keeps_link_style():
+    # given a project guarded with relative links, against its config
+    cg = _guard(source_dir=TEST_PROJ, is_relative=True)
+    (TEST_PROJ / ".envrc").unlink()
+
+    # when
+    assert _relink_all() == {TEST_PROJ: "relinked"}
+
+    # then the new link is relative as well
+    assert not os.readlink(TEST_PROJ / ".envrc").startswith("/")
+    assert (TEST_PROJ / ".envrc").resolve() == cg.target_dir / ".envrc"
+
+
+def test__relink_all_refuses_to_overwrite_files():
+    # given a guarded project where a guarded file has been replaced by a regular file
+    _ = _guard(source_dir=TEST_PROJ)
+    (TEST_PROJ / ".envrc").unlink()
+    (TEST_PROJ / ".envrc").write_text("local")
+
+    # when
+    results = _relink_all()
+
+    # then
+    assert results == {TEST_PROJ: "failed"}
+    assert (TEST_PROJ / ".envrc").read_text() == "local"
+
+
+def _fake_sentinel(sentinel: str, source_dir: Path, timestamp: str) -> None:
+    target_dir = config.confguard_path / sentinel
+    target_dir.mkdir()
+    (target_dir / f".{sentinel}.confguard").symlink_to(source_dir)
+    (target_dir / ".confguard.bkp").write_text(
+        f'[_internal_]\nsentinel = "{sentinel}"\ntimestamp = {timestamp}\n'
+    )
+
+
+def test__sort_guarded_by_invalid_date():
+    # given sentinels with odd or unreadable timestamps
+    _fake_sentinel("a-12345678", Path("/y/a"), "2023-01-01T00:00:00")
+    _fake_sentinel("b-12345678", Path("/z/b"), "2023-01-01")
+    _fake_sentinel("c-12345678", Path("/x/c"), "2023-02-01T00:00:00+02:00")
+    _fake_sentinel("d-12345678", Path("/x/d"), "not a date")
+    _fake_sentinel("e-12345678", Path("/x/e"), "2023-03-01T00:00:00")
+    (config.confguard_path / "e-12345678/.confguard.bkp").write_bytes(b"\xff\xfe")
+
+    guarded = _sort_guarded(_list_guarded(), SortKey.date)
+
+    # then unreadable timestamps sort as oldest
+    assert [cg.sentinel for cg in guarded[:2]] == ["c-12345678", "a-12345678"]
+    assert sorted(cg.sentinel for cg in guarded[2:]) == [
+        "b-12345678",
+        "d-12345678",
+        "e-12345678",
+    ]
+
+
+@pytest.mark.parametrize(
+    ("sort", "reverse", "expected"),
+    (
+        (SortKey.date, False, ["b-12345678", "c-12345678", "a-12345678"]),
+        (SortKey.date, True, ["a-12345678", "c-12345678", "b-12345678"]),
+        (SortKey.name, False, ["a-12345678", "b-12345678", "c-12345678"]),
+        (SortKey.source, False, ["c-12345678", "a-12345678", "b-12345678"]),
+        (SortKey.source, True, ["b-12345678", "a-12345678", "c-12345678"]),
+    ),
+)
+def test__sort_guarded(sort, reverse, expected):
+    _fake_sentinel("a-12345678", Path("/y/a"), "2023-01-01T00:00:00")
+    _fake_sentinel("b-12345678", Path("/z/b"), "2023-03-01T00:00:00")
+    _fake_sentinel("c-12345678", Path("/x/c"), "2023-02-01T00:00:00")
+
+    guarded = _sort_guarded(_list_guarded(), sort, reverse)
+
+    assert [cg.sentinel for cg in guarded] == expected
+
+
+@pytest.mark.parametrize("relative", (False, True))
+def test__guard_copy_mode(relative):
+    # given a project with only an .envrc to guard
+    (TEST_PROJ / CONFGUARD_CONFIG_FILE).write_text("[config]\ntargets = ['.envrc']\n")
+    content = (TEST_PROJ / ".envrc").read_text()
+
+    # when guarded in copy mode
+    cg = _guard(source_dir=TEST_PROJ, is_relative=relative, copy=True)
+
+    # then a stub sources the guarded file, no symlink
+    stub = TEST_PROJ / ".envrc"
+    assert stub.is_file() and not stub.is_symlink()
+    assert stub.read_text().splitlines()[1].startswith("source_env ")
+    assert (cg.target_dir / ".envrc").read_text() == content
+    saved = TomlRepoConfGuard(source_dir=TEST_PROJ).get()
+    assert saved.is_copy
+    assert saved.file_status() == {".envrc": "ok"}
+    assert _verify_guarded(cg) == []
+
+    # when the stub is lost, relink-all recreates it
+    stub.unlink()
+    assert _relink_all() == {TEST_PROJ: "relinked"}
+    assert saved.file_status() == {".envrc": "ok"}
+
+    # when un-guarded, the original file is back
+    _unguard(source_dir=TEST_PROJ)
+    assert stub.read_text() == content
+    assert not cg.target_dir.exists()
+    assert not TomlRepoConfGuard(source_dir=TEST_PROJ).get().is_copy
+
+
+def test__guard_copy_mode_refuses_other_targets():
+    # when guarding a project with directory targets in copy mode
+    with pytest.raises(Exit):
+        _guard(source_dir=TEST_PROJ, copy=True)
+
+    # then nothing has been touched
+    assert list(config.confguard_path.glob("*")) == []
+    assert (TEST_PROJ / ".run").is_dir()
+
+
+def test__unguard_with_missing_sentinel_file():
+    # given a guarded project whose sentinel lost a file
+    cg = _guard(source_dir=TEST_PROJ)
+    (cg.target_dir / "xxx/xxx.txt").unlink()
+
+    # when
+    with pytest.raises(Exit):
+        _unguard(source_dir=TEST_PROJ)
+
+    # then nothing has been changed
+    assert (TEST_PROJ / ".envrc").is_symlink()
+    assert (TEST_PROJ / ".run").is_symlink()
+    assert (cg.target_dir / ".envrc").is_file()
+    assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel == cg.sentinel
+
+
+def test__unguard_with_file_instead_of_link():
+    # given a guarded project where a link has been replaced by a regular file
+    cg = _guard(source_dir=TEST_PROJ)
+    (TEST_PROJ / "xxx/xxx.txt").unlink()
+    (TEST_PROJ / "xxx/xxx.txt").write_text("local")
+
+    # when
+    with pytest.raises(Exit):
+        _unguard(source_dir=TEST_PROJ)
+
+    # then nothing has been changed
+    assert (TEST_PROJ / ".envrc").is_symlink()
+    assert (TEST_PROJ / "xxx/xxx.txt").read_text() == "local"
+    assert (cg.target_dir / "xxx/xxx.txt").is_file()
+    assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel == cg.sentinel
+
+
+def test__unguard_with_target_missing_at_guard_time():
+    # given a target which did not exist when the project was guarded
+    (TEST_PROJ / "xxx/xxx.txt").unlink()
+    _ = _guard(source_dir=TEST_PROJ)
+
+    assert not (TEST_PROJ / "xxx/xxx.txt").is_symlink()
+
+    # when
+    cg = _unguard(source_dir=TEST_PROJ)
+
+    # then
+    assert cg.sentinel is None
+    assert (TEST_PROJ / ".envrc").is_file()
+
+
+def test__guard_not_writable(monkeypatch):
+    # given the project directory cannot be written to
+    monkeypatch.setattr(
+        confguard.model, "_is_writable", lambda dir_: dir_ != TEST_PROJ / "xxx"
+    )
+
+    # when
+    with pytest.raises(Exit):
+        _guard(source_dir=TEST_PROJ)
+
+    # then no sentinel has been created
+    assert list(config.confguard_path.glob("*")) == []
+    assert (TEST_PROJ / ".envrc").is_file()
+    assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel is None
+
+
+@pytest.mark.parametrize("args", (["--read-only"], []))
+def test_read_only(caplog, args):
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    env = {} if args else {"CONFGUARD_READONLY": "1"}
+    result = runner.invoke(app, [*args, "guard", str(TEST_PROJ)], env=env)
+    assert result.exit_code == 6
+    assert "Read-only mode" in result.output
+    assert (TEST_PROJ / ".envrc").is_file()
+    assert list(config.confguard_path.glob("*")) == []
+
+    result = runner.invoke(app, [*args, "list"], env=env)
+    assert result.exit_code == 0
+
+
+def test_read_only_commands(caplog):
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    commands = typer.main.get_command(app).commands
+    for name in commands:
+        result = runner.invoke(app, ["--read-only", name, str(TEST_PROJ)])
+        if name in READ_ONLY_COMMANDS:
+            assert "Read-only mode" not in result.output
+        else:
+            assert result.exit_code == 6, name
+    assert set(READ_ONLY_COMMANDS) <= set(commands)
+
+
+def test_read_only_verify_fix(caplog):
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    _guard(source_dir=TEST_PROJ)
+    (TEST_PROJ / ".envrc").unlink()
+    result = runner.invoke(app, ["--read-only", "verify", "--fix", str(TEST_PROJ)])
+    assert result.exit_code == 6
+    assert "Read-only mode" in result.output
+    assert not (TEST_PROJ / ".envrc").exists()
+
+
+def test_trace_file(caplog, tmp_path):
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    trace_file = tmp_path / "trace.log"
+    try:
+        result = runner.invoke(
+            app, ["--trace-file", str(trace_file), "guard", str(TEST_PROJ)]
+        )
+    finally:
+        root = logging.getLogger()
+        for handler in [h for h in root.handlers if isinstance(h, logging.FileHandler)]:
+            root.removeHandler(handler)
+            handler.close()
+    assert result.exit_code == 0
+    trace = trace_file.read_text()
+    assert f"Guarding {TEST_PROJ}" in trace
+    assert "DEBUG" in trace
+    assert "move_files took" in trace
+
+
+def test__guard_invalid_source_date_epoch(monkeypatch):
+    monkeypatch.setenv("SOURCE_DATE_EPOCH", "yesterday")
+
+    with pytest.raises(Exit):
+        _guard(source_dir=TEST_PROJ)
+
+    # then nothing has been touched
+    assert list(config.confguard_path.glob("*")) == []
+    assert (TEST_PROJ / ".envrc").is_file()
+    assert not (TEST_PROJ / ".envrc").is_symlink()
+    assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel is None
+
+
+def test__guard_sentinel_by_path_exists(monkeypatch):
+    # given a project guarded with path-derived sentinel names
+    monkeypatch.setattr(config, "confguard_sentinel_by_path", True)
+    cg = _guard(source_dir=TEST_PROJ)
+    secret = (cg.target_dir / ".envrc").read_text()
+
+    # when its .confguard and links are lost and it is guarded again
+    for t in cg.targets:
+        (TEST_PROJ / t).unlink(missing_ok=True)
+    (TEST_PROJ / ".envrc").write_text("export FOO=new\n")
+    (TEST_PROJ / CONFGUARD_CONFIG_FILE).write_text("[config]\ntargets = ['.envrc']\n")
+    with pytest.raises(Exit) as e:
+        _guard(source_dir=TEST_PROJ)
+
+    # then the existing sentinel is untouched
+    assert e.value.exit_code == 4
+    assert (cg.target_dir / ".envrc").read_text() == secret
+    assert (TEST_PROJ / ".envrc").read_text() == "export FOO=new\n"
+    assert [g.sentinel for g in _list_guarded()] == [cg.sentinel]
+
+
+def test__guard_with_lost_state():
+    # given a guarded project whose .confguard lost its state
+    cg = _guard(source_dir=TEST_PROJ)
+    shutil.copyfile(REF_PROJ / CONFGUARD_CONFIG_FILE, TEST_PROJ / CONFGUARD_CONFIG_FILE)
+
+    # when
+    with pytest.raises(Exit) as e:
+        _guard(source_dir=TEST_PROJ)
+
+    # then it is not guarded a second time
+    assert e.value.exit_code == 4
+    assert [p.name for p in config.confguard_path.glob("*")] == [cg.sentinel]
+    assert (TEST_PROJ / ".envrc").resolve() == cg.target_dir / ".envrc"
+
+
+def test__guard_protected_path(monkeypatch):
+    # given the project directory is protected
+    monkeypatch.setattr(config, "confguard_protected_paths", [TEST_PROJ.parent])
+
+    # when
+    with pytest.raises(Exit):
+        _guard(source_dir=TEST_PROJ)
+
+    # then nothing has been touched
+    assert list(config.confguard_path.glob("*")) == []
+    assert (TEST_PROJ / ".envrc").is_file()
+    assert TomlRepoConfGuard(source_dir=TEST_PROJ).get().sentinel is None
+
+
+def test__guard_with_changed_targets_uses_configured_link_style():
+    # given a project guarded with relative links, against its config
+    _ = _guard(source_dir=TEST_PROJ, is_relative=True)
+    path = TEST_PROJ / CONFGUARD_CONFIG_FILE
+    toml = tomlkit.parse(path.read_text())
+    toml["config"]["targets"] = [".envrc"]
+    path.write_text(tomlkit.dumps(toml))
+
+    # when re-guarded without --relative
+    _ = _guard(source_dir=TEST_PROJ)
+
+    # then the link follows `relative = false`
+    assert os.readlink(TEST_PROJ / ".envrc").startswith("/")
+    assert not TomlRepoConfGuard(source_dir=TEST_PROJ).get().is_relative
+
+
+def test__guard_protected_path_with_changed_targets(monkeypatch):
+    # given a guarded project whose targets are changed, now in a protected path
+    cg = _guard(source_dir=TEST_PROJ)
+    path = TEST_PROJ / CONFGUARD_CONFIG_FILE
+    toml = tomlkit.parse(path.read_text())
+    toml["config"]["targets"] = [".envrc"]
+    path.write_text(tomlkit.dumps(toml))
+    monkeypatch.setattr(config, "confguard_protected_paths", [TEST_PROJ.parent])
+
+    # when
+    with pytest.raises(Exit):
+        _guard(source_dir=TEST_PROJ)
+
+    # then it has not been un-guarded
+    assert (TEST_PROJ / ".envrc").is_symlink()
+    assert (TEST_PROJ / ".run").is_symlink()
+    assert (cg.target_dir / ".envrc").is_file()
+
+
+def test__unguard_preserves_modes_in_directories():
+    # given a guarded directory with a nested file with non-default mode
+    nested = TEST_PROJ / ".run" / "secret" / "key.txt"
+    nested.parent.mkdir()
+    nested.write_text("key")
+    nested.chmod(0o600)
+    nested.parent.chmod(0o700)
+    _ = _guard(source_dir=TEST_PROJ)
+
+    # when
+    _ = _unguard(source_dir=TEST_PROJ)
+
+    # then
+    assert not (TEST_PROJ / ".run").is_symlink()
+    assert stat.S_IMODE(nested.stat().st_mode) == 0o600
+    assert stat.S_IMODE(nested.parent.stat().st_mode) == 0o700
+
+
 def test__guard_already_guarded(caplog, capsys):
     cg = _guard(source_dir=TEST_PROJ)
 
@@ -176,6 +1029,51 @@ def test__find_and_link():
     assert Path(confguard / f".{cg.sentinel}.confguard").resolve() == TEST_PROJ
 
 
+def test__find_and_link_with_same_named_project():
+    # given a guarded project and a same-named project guarded from elsewhere
+    cg = _guard(source_dir=TEST_PROJ)
+    _fake_sentinel("test_proj-aaaaaaaa", Path("/elsewhere/test_proj"), "2023-01-01")
+    (TEST_PROJ / CONFGUARD_CONFIG_FILE).unlink()
+
+    # when project is relinked
+    cg = _find_and_link(source_dir=TEST_PROJ)
+
+    # then its own sentinel is used
+    assert (TEST_PROJ / ".envrc").resolve() == cg.target_dir / ".envrc"
+    assert (config.confguard_path / "test_proj-aaaaaaaa").exists()
+
+
+def test__find_and_link_moved_with_same_named_project():
+    # given two same-named projects guarded elsewhere, one of them moved here
+    _fake_sentinel("test_proj-aaaaaaaa", Path("/w1/test_proj"), "2023-01-01T00:00:00")
+    _fake_sentinel("test_proj-bbbbbbbb", Path("/w2/test_proj"), "2023-01-01T00:00:00")
+
+    # when project is relinked
+    with pytest.raises(Exit) as e:
+        _find_and_link(source_dir=TEST_PROJ)
+
+    # then it is ambiguous, not missing
+    assert e.value.exit_code == 1
+    assert (TEST_PROJ / ".envrc").is_file()
+    assert len(list(config.confguard_path.glob("*"))) == 2
+
+
+def test__find_and_link_with_foreign_backup():
+    # given a guarded project with missing .confguard file
+    cg = _guard(source_dir=TEST_PROJ)
+    (TEST_PROJ / CONFGUARD_CONFIG_FILE).unlink()
+    # and a backup which records a different sentinel
+    toml_bkp = (cg.target_dir / CONFGUARD_CONFIG_FILE).with_suffix(".bkp")
+    toml_bkp.write_text(toml_bkp.read_text().replace(cg.sentinel, "other-12345678"))
+
+    # when project is relinked
+    with pytest.raises(Exit):
+        _find_and_link(source_dir=TEST_PROJ)
+
+    # then the foreign configuration has not been restored
+    assert not (TEST_PROJ / CONFGUARD_CONFIG_FILE).exists()
+
+
 def test_restore_toml():
     # given a guarded project with missing .confguard file
     cg = _guard(source_dir=TEST_PROJ)
@@ -228,3 +1126,19 @@ def test__guard_relative():
 
     # then backlink created
     assert Path(confguard / f".{cg.sentinel}.confguard").resolve() == TEST_PROJ
+
+
+def test__list_guarded():
+    # given a guarded project and stray entries in the confguard directory
+    cg = _guard(source_dir=TEST_PROJ)
+    (config.confguard_path / "stray.txt").write_text("stray")
+    (config.confguard_path / "stray-12345678").mkdir()
+
+    # when
+    guarded = _list_guarded()
+
+    # then only the real sentinel is reported
+    assert len(guarded) == 1
+    assert guarded[0].sentinel == cg.sentinel
+    assert guarded[0].target_dir == cg.target_dir
+    assert guarded[0].source_dir == TEST_PROJ
@@ -0,0 +1,40 @@
+from pathlib import Path
+
+import pytest
+
+from confguard.environment import Environment, default_confguard_path, load_config
+
+
+def test_default_confguard_path_xdg(monkeypatch, tmp_path):
+    monkeypatch.setenv("XDG_DATA_HOME", str(tmp_path))
+    assert default_confguard_path() == tmp_path / "confguard"
+
+
+def test_default_confguard_path_without_xdg(monkeypatch):
+    monkeypatch.setenv("XDG_DATA_HOME", "relative/is/invalid")
+    assert default_confguard_path() == Path.home() / ".local/share/confguard"
+
+
+def test_confguard_path_from_env(monkeypatch, tmp_path):
+    monkeypatch.setenv("XDG_DATA_HOME", str(tmp_path / "xdg"))
+    monkeypatch.setenv("CONFGUARD_PATH", str(tmp_path / "store"))
+    assert Environment().confguard_path == tmp_path / "store"
+
+
+def test_confguard_path_defaults_to_xdg(monkeypatch, tmp_path):
+    monkeypatch.setenv("XDG_DATA_HOME", str(tmp_path))
+    monkeypatch.delenv("CONFGUARD_PATH", raising=False)
+    env = Environment()
+    assert env.confguard_path == tmp_path / "confguard"
+    assert env.confguard_path.is_dir()
+
+
+@pytest.mark.parametrize(
+    ("name", "value"),
+    (("CONFGUARD_PROTECTED_PATHS", "not json"), ("CONFGUARD_RELATIVE", "maybe")),
+)
+def test_load_config_invalid(monkeypatch, capsys, name, value):
+    monkeypatch.setenv(name, value)
+    with pytest.raises(SystemExit):
+        load_config()
+    assert name.lower() in capsys.readouterr().err.lower()
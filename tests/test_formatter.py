@@ -0,0 +1,34 @@
+import csv
+import io
+import json
+
+import pytest
+
+from confguard.formatter import OutputFormat, render
+
+ROWS = [
+    {"sentinel": "a-12345678", "source_dir": "/x/a", "target_dir": "/cg/a-12345678"},
+    {"sentinel": "b-12345678", "source_dir": "/x/b,c", "target_dir": "/cg/b-12345678"},
+]
+
+
+def test_render_json():
+    assert json.loads(render(ROWS, OutputFormat.json)) == ROWS
+
+
+def test_render_csv_quotes_commas():
+    output = render(ROWS, OutputFormat.csv)
+    assert '"/x/b,c"' in output
+    assert list(csv.DictReader(io.StringIO(output))) == ROWS
+
+
+def test_render_table():
+    lines = render(ROWS, OutputFormat.table).splitlines()
+    assert lines[0].split() == ["SENTINEL", "SOURCE_DIR", "TARGET_DIR"]
+    assert lines[1].split() == ["a-12345678", "/x/a", "/cg/a-12345678"]
+    assert lines[1].index("/x/a") == lines[0].index("SOURCE_DIR")
+
+
+@pytest.mark.parametrize("fmt", (OutputFormat.table, OutputFormat.csv))
+def test_render_empty(fmt):
+    assert render([], fmt) == ""
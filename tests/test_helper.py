@@ -6,6 +6,10 @@ import pytest
 # noinspection PyProtectedMember
 from confguard.helper import (
     _create_relative_path,
+    _find_linked_worktree,
+    _is_protected,
+    _is_writable,
+    _timed,
     deserialize_from_base64,
     serialize_to_base64,
 )
@@ -104,3 +108,46 @@ aARoBWUu
     obj = deserialize_from_base64(serialized)
     print(f"\n{obj}")
     assert obj == FILES
+
+
+class TestFindLinkedWorktree:
+    def test_linked_worktree(self, tmp_path):
+        (tmp_path / ".git").write_text("gitdir: /repo/.git/worktrees/wt\n")
+        (tmp_path / "sub").mkdir()
+        assert _find_linked_worktree(tmp_path / "sub") == tmp_path
+
+    def test_main_checkout(self, tmp_path):
+        (tmp_path / ".git").mkdir()
+        assert _find_linked_worktree(tmp_path) is None
+
+    def test_submodule(self, tmp_path):
+        (tmp_path / ".git").write_text("gitdir: ../.git/modules/sub\n")
+        assert _find_linked_worktree(tmp_path) is None
+
+
+def test_timed(caplog):
+    caplog.set_level(logging.DEBUG)
+    with _timed("phase"):
+        pass
+    assert "phase took" in caplog.text
+
+
+def test_is_writable(tmp_path):
+    assert _is_writable(tmp_path)
+    assert not _is_writable(tmp_path / "does-not-exist")
+
+
+@pytest.mark.parametrize(
+    ("path", "expected"),
+    (
+        ("/", True),
+        ("/etc", True),
+        ("/etc/nginx", True),
+        (str(Path.home()), True),
+        (str(Path.home() / "dev/project"), False),
+        ("/srv/project", False),
+    ),
+)
+def test_is_protected(path, expected):
+    protected = [Path("/"), Path.home(), Path("/etc")]
+    assert _is_protected(Path(path), protected) == expected
@@ -0,0 +1,64 @@
+import json
+from pathlib import Path
+
+from typer.testing import CliRunner
+
+from confguard.environment import CONFGUARD_CONFIG_FILE, config
+from confguard.main import app
+from tests.conftest import TEST_PROJ
+
+runner = CliRunner()
+
+TARGETS = [".envrc", ".run", "xxx/xxx.txt"]
+
+
+def test_lifecycle(caplog):
+    """guard -> list -> show -> lose links -> relink-all -> unguard"""
+    caplog.set_level(
+        100000
+    )  # BUG: https://github.com/pallets/click/issues/824#issuecomment-562581313
+    envrc = (TEST_PROJ / ".envrc").read_text()
+
+    # guard: targets are moved into the sentinel and replaced by links
+    result = runner.invoke(app, ["guard", "--print-path", str(TEST_PROJ)])
+    assert result.exit_code == 0
+    sentinel_dir = Path(result.stdout.strip())
+    for t in TARGETS:
+        assert (TEST_PROJ / t).is_symlink()
+        assert (TEST_PROJ / t).resolve() == sentinel_dir / t
+    assert (sentinel_dir / f".{sentinel_dir.name}.confguard").resolve() == TEST_PROJ
+
+    # list: exactly this project is guarded
+    result = runner.invoke(app, ["list", "--format", "json"])
+    assert result.exit_code == 0
+    rows = json.loads(result.stdout)
+    assert [(r["sentinel"], r["source_dir"]) for r in rows] == [
+        (sentinel_dir.name, str(TEST_PROJ))
+    ]
+
+    # show: all links are fine
+    result = runner.invoke(app, ["show", "--format", "json", str(TEST_PROJ)])
+    assert result.exit_code == 0
+    assert {r["status"] for r in json.loads(result.stdout)} == {"ok"}
+
+    # links and .confguard get lost, e.g. by a restore from backup
+    (TEST_PROJ / CONFGUARD_CONFIG_FILE).unlink()
+    for t in TARGETS:
+        (TEST_PROJ / t).unlink()
+
+    # relink-all: links are recreated into the same sentinel
+    result = runner.invoke(app, ["relink-all"])
+    assert result.exit_code == 0
+    assert f"relinked: {TEST_PROJ}" in result.stdout
+    for t in TARGETS:
+        assert (TEST_PROJ / t).resolve() == sentinel_dir / t
+
+    # unguard: original files are back, the sentinel is gone
+    result = runner.invoke(app, ["unguard", str(TEST_PROJ)])
+    assert result.exit_code == 0
+    for t in TARGETS:
+        assert not (TEST_PROJ / t).is_symlink()
+        assert (TEST_PROJ / t).exists()
+    assert (TEST_PROJ / ".envrc").read_text() == envrc
+    assert not sentinel_dir.exists()
+    assert list(Path(config.confguard_path).glob("*")) == []
@@ -3,8 +3,12 @@ import shutil
 
 import pytest
 
-from confguard.environment import CONFGUARD_BKP_DIR, CONFGUARD_CONFIG_FILE
-from confguard.exceptions import BackupExistError
+from confguard.environment import CONFGUARD_BKP_DIR, CONFGUARD_CONFIG_FILE, config
+from confguard.exceptions import (
+    BackupExistError,
+    SentinelExistsError,
+    TargetOutsideBaseError,
+)
 from confguard.model import ConfGuard
 from tests.conftest import TARGET_DIR, TEST_PROJ
 
@@ -21,12 +25,47 @@ class TestSentinel:
         cg.create_sentinel()
         assert "test_proj" in cg.sentinel
 
+    def test_create_sentinel_by_path(self, monkeypatch):
+        monkeypatch.setattr(config, "confguard_sentinel_by_path", True)
+        sentinels = set()
+        for _ in range(2):
+            cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc"])
+            cg.create_sentinel()
+            sentinels.add(cg.sentinel)
+        other = ConfGuard(source_dir=TEST_PROJ / "xxx" / "test_proj", targets=[])
+        other.create_sentinel()
+        assert len(sentinels) == 1
+        assert other.sentinel not in sentinels
+
+    def test_create_sentinel_exists(self, monkeypatch):
+        monkeypatch.setattr(config, "confguard_sentinel_by_path", True)
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc"])
+        cg.create_sentinel()
+        cg.target_dir.mkdir()
+
+        again = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc"])
+        with pytest.raises(SentinelExistsError):
+            again.create_sentinel()
+        assert again.sentinel is None
+
     def test_remove_sentinel(self):
         cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc", ".run", "xxx/xxx.txt"])
         cg.create_sentinel()
         cg.remove_sentinel()
         assert cg.sentinel is None
 
+    @pytest.mark.parametrize("sentinel", ("../outside", "."))
+    def test_check_target_dir_outside_base(self, sentinel):
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc"])
+        cg.target_dir = config.confguard_path / sentinel
+        with pytest.raises(TargetOutsideBaseError):
+            cg.check_target_dir()
+
+    def test_check_target_dir(self):
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc"])
+        cg.create_sentinel()
+        cg.check_target_dir()
+
     def test_backup_toml(self):
         cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc", ".run", "xxx/xxx.txt"])
         cg.create_sentinel()
@@ -54,6 +93,17 @@ class TestFiles:
             assert cg.target_dir.joinpath(t).exists()
             assert not cg.source_dir.joinpath(t).exists()
 
+    def test_move_files_does_not_overwrite(self):
+        cg = ConfGuard(source_dir=TEST_PROJ, targets=[".envrc"])
+        cg.create_sentinel()
+        cg.target_dir.mkdir()
+        (cg.target_dir / ".envrc").write_text("export SECRET=original\n")
+
+        with pytest.raises(FileExistsError):
+            cg.move_files()
+        assert (cg.target_dir / ".envrc").read_text() == "export SECRET=original\n"
+        assert (TEST_PROJ / ".envrc").is_file()
+
     @pytest.mark.parametrize(
         "targets",
         (